@@ -0,0 +1,54 @@
+use std::time::Duration;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use fishing_jigsaw::deterministic::Deterministic;
+use fishing_jigsaw::figure_set::FigureSet;
+use fishing_jigsaw::jigsaw::{Dimensions, Orientations, STANDARD};
+
+// serial and parallel builds of the same table, small enough for criterion to
+// take a fair number of samples.
+fn build(c: &mut Criterion) {
+    let dims = Dimensions::new(4, 4);
+    let mut group = c.benchmark_group("build 4x4");
+    group.sample_size(10);
+
+    for orientations in [Orientations::Fixed, Orientations::RotationsAndReflections] {
+        group.bench_function(format!("serial {orientations:?}"), |b| {
+            b.iter(|| {
+                let mut strategy = Deterministic::with_orientations(dims, FigureSet::standard(), orientations);
+                strategy.run();
+                strategy
+            })
+        });
+        group.bench_function(format!("parallel {orientations:?}"), |b| {
+            b.iter(|| {
+                let mut strategy = Deterministic::with_orientations(dims, FigureSet::standard(), orientations);
+                strategy.run_parallel();
+                strategy
+            })
+        });
+    }
+
+    group.finish();
+}
+
+// the table the app and the command line build, with the fewest samples
+// criterion takes since every build takes over ten seconds.
+fn build_standard(c: &mut Criterion) {
+    let mut group = c.benchmark_group("build 4x6");
+    group.sample_size(10);
+    group.measurement_time(Duration::from_secs(150));
+
+    group.bench_function("parallel Fixed", |b| {
+        b.iter(|| {
+            let mut strategy = Deterministic::with_figures(STANDARD, FigureSet::standard());
+            strategy.run_parallel();
+            strategy
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, build, build_standard);
+criterion_main!(benches);
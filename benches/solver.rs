@@ -0,0 +1,54 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use fishing_jigsaw::deterministic::Deterministic;
+use fishing_jigsaw::distribution::round_distribution;
+use fishing_jigsaw::jigsaw::{FigureDistribution, Jigsaw, STANDARD};
+use fishing_jigsaw::solver::Solver;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+const GAMES: usize = 1024;
+
+// unfinished games on random boards of the standard size, the same ones on
+// every run.
+fn games() -> Vec<Jigsaw> {
+    let mut rng = StdRng::seed_from_u64(0);
+    let figures = FigureDistribution::default();
+    let terminal = STANDARD.terminal_state::<u32>();
+
+    std::iter::repeat_with(|| {
+        let mut game = Jigsaw::new(STANDARD);
+        game.board = rng.gen::<u32>() & rng.gen::<u32>() & terminal;
+        game.set_random_figure(&mut rng, &figures);
+        game
+    })
+    .filter(|game| !game.has_finished())
+    .take(GAMES)
+    .collect()
+}
+
+// table lookups and legality checks over a batch of games, then the exact
+// distribution of the final round from the empty board, the slowest query
+// the app makes.
+fn solver(c: &mut Criterion) {
+    let mut strategy = Deterministic::new();
+    strategy.run_parallel();
+    let games = games();
+
+    c.bench_function("solve 1024 games", |b| {
+        b.iter(|| games.iter().map(|game| strategy.solve(game).index(STANDARD) as usize).sum::<usize>())
+    });
+
+    c.bench_function("legal actions 1024 games", |b| {
+        b.iter(|| games.iter().map(|game| game.legal_actions().len()).sum::<usize>())
+    });
+
+    let mut group = c.benchmark_group("simulation");
+    group.sample_size(10);
+    group.bench_function("round distribution", |b| {
+        let game = Jigsaw::new(STANDARD);
+        b.iter(|| round_distribution(&strategy, &game, strategy.figures(), 64))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, solver);
+criterion_main!(benches);
@@ -1,37 +1,151 @@
+use std::ops::ControlFlow;
+use std::sync::atomic::{AtomicBool, AtomicU8, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
 use egui::Color32;
-use rand::SeedableRng;
 
-use crate::jigsaw::{Jigsaw, FIGURES};
-use crate::jigsaw;
+use crate::figure_set::FigureSet;
+use crate::jigsaw::{Action, Figure, Jigsaw, Orientations, Placement, STANDARD};
 
 use crate::deterministic::Deterministic;
+use crate::distribution;
+use crate::history::{Change, History, Turn};
+use crate::practice::{Grade, Session};
 use crate::solver::Solver;
 
+#[derive(Clone, Copy, PartialEq)]
+enum Mode {
+    Analysis,
+    Practice,
+}
+
 pub struct App {
     state: Jigsaw,
-    strategy: Deterministic,
+    mode: Mode,
+    practice: Session,
+    history: History,
+    strategy: Option<Deterministic>,
+    build: Option<TableBuild>,
     distribution: Distribution,
+    figures: FigureSet,
+    figures_path: String,
+    figures_error: Option<String>,
 }
 
-impl Default for App {
-    fn default() -> Self {
-        let mut strategy = Deterministic::new();
-        strategy.run();
+const UNDO_SHORTCUT: egui::KeyboardShortcut =
+    egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::Z);
+const REDO_SHORTCUT: egui::KeyboardShortcut =
+    egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::Y);
+const REDO_SHIFT_SHORTCUT: egui::KeyboardShortcut = egui::KeyboardShortcut::new(
+    egui::Modifiers::COMMAND.plus(egui::Modifiers::SHIFT),
+    egui::Key::Z,
+);
+
+/// Solved table written next to the executable's working directory, so that
+/// only the first launch has to build it.
+const TABLE_CACHE: &str = "fishing-jigsaw.table";
+
+fn load_strategy(set: FigureSet, progress: &AtomicU8, memory: &AtomicUsize, cancel: &AtomicBool) -> Option<Deterministic> {
+    // the cache may have been written by the command line tool for another
+    // rule set, the app only plays figures as drawn.
+    if let Ok(strategy) = Deterministic::load_from_file(TABLE_CACHE) {
+        let fixed = strategy.orientations() == Orientations::Fixed;
+        if strategy.figure_set() == &set && strategy.dims() == STANDARD && fixed {
+            return Some(strategy);
+        }
+    }
+
+    let mut strategy = Deterministic::with_figures(STANDARD, set);
+    memory.store(strategy.memory(), Ordering::Relaxed);
+    let flow = strategy.run_parallel_with_progress(|layers| {
+        progress.store(layers, Ordering::Relaxed);
+        if cancel.load(Ordering::Relaxed) {
+            ControlFlow::Break(())
+        } else {
+            ControlFlow::Continue(())
+        }
+    });
+    if flow.is_break() {
+        return None;
+    }
+
+    // the cache is only an optimization, failing to write it is not an error.
+    let _ = strategy.save_to_file(TABLE_CACHE);
+    Some(strategy)
+}
+
+/// Table being loaded or built on a background thread, so the window stays
+/// responsive in the meantime.
+struct TableBuild {
+    progress: Arc<AtomicU8>,
+    memory: Arc<AtomicUsize>,
+    cancel: Arc<AtomicBool>,
+    handle: JoinHandle<Option<Deterministic>>,
+}
 
-        let mut dist = Distribution::default();
-        dist.compute(&strategy);
+impl TableBuild {
+    fn spawn(set: FigureSet) -> Self {
+        let progress = Arc::new(AtomicU8::new(0));
+        let memory = Arc::new(AtomicUsize::new(0));
+        let cancel = Arc::new(AtomicBool::new(false));
+
+        let handle = {
+            let (progress, memory, cancel) = (progress.clone(), memory.clone(), cancel.clone());
+            thread::spawn(move || load_strategy(set, &progress, &memory, &cancel))
+        };
 
+        Self {
+            progress,
+            memory,
+            cancel,
+            handle,
+        }
+    }
+
+    fn fraction(&self) -> f32 {
+        self.progress.load(Ordering::Relaxed) as f32 / STANDARD.cells() as f32
+    }
+
+    fn ui(&self, ui: &mut egui::Ui) {
+        ui.horizontal_wrapped(|ui| {
+            let text = "Building the strategy table. \
+                This only happens on the first launch, \
+                later launches load it from disk.";
+            ui.label(text);
+        });
+
+        // zero until the table has been allocated.
+        let memory = self.memory.load(Ordering::Relaxed);
+        if memory > 0 {
+            ui.label(format!("The table uses {} MB of memory.", memory.div_ceil(1 << 20)));
+        }
+        ui.add_space(8.0);
+        ui.add(egui::ProgressBar::new(self.fraction()).show_percentage());
+    }
+}
+
+impl Default for App {
+    fn default() -> Self {
         Self {
             state: Jigsaw::default(),
-            strategy: strategy,
-            distribution: dist,
+            mode: Mode::Analysis,
+            practice: Session::default(),
+            history: History::default(),
+            strategy: None,
+            build: Some(TableBuild::spawn(FigureSet::standard())),
+            distribution: Distribution::default(),
+            figures: FigureSet::standard(),
+            figures_path: String::new(),
+            figures_error: None,
         }
     }
 }
 
 impl App {
     /// Called once before the first frame.
-    pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
+    pub fn new(_cc: &eframe::CreationContext<'_>) -> Self {
         // This is also where you can customize the look and feel of egui using
         // `cc.egui_ctx.set_visuals` and `cc.egui_ctx.set_fonts`.
 
@@ -40,9 +154,8 @@ impl App {
 }
 
 struct Distribution {
-    data: Vec<usize>,
-    test_size: usize,
-    seed: u64,
+    data: Vec<f64>,
+    max_round: u8,
     state: Jigsaw,
 }
 
@@ -50,8 +163,7 @@ impl Default for Distribution {
     fn default() -> Self {
         Self {
             data: vec![],
-            test_size: 4096,
-            seed: 2024,
+            max_round: 64,
             state: Jigsaw::default(),
         }
     }
@@ -63,30 +175,10 @@ impl Distribution {
     }
 
     fn compute(&mut self, s: &Deterministic) {
-        let mut rng = SeedableRng::seed_from_u64(self.seed);
-
-        self.data = vec![0; 30];
-
-        for _ in 0..self.test_size {
-            // let mut game = Jigsaw::default();
-            let mut game = self.state.clone();
-            while !game.has_finished() {
-                let input = s.solve(&game);
-                game.perform_action(input);
-                game.set_random_figure(&mut rng);
-            }
-            let r = game.round as usize;
-
-            while r >= self.data.len() {
-                self.data.push(0);
-            }
-
-            self.data[r] += 1;
-        }
+        self.data = distribution::round_distribution(s, &self.state, s.figures(), self.max_round);
     }
 
     fn ui(&self, ui: &mut egui::Ui) {
-        let n_test = self.test_size;
         let dist = &self.data;
 
         ui.vertical_centered(|ui| {
@@ -99,12 +191,12 @@ impl Distribution {
                 .show_grid(false)
                 .show_axes(false)
                 .show(ui, |ui| {
+                    // the tail of the distribution is too thin to be seen.
                     let bars = dist
                         .iter()
+                        .take(30)
                         .enumerate()
-                        .map(|(i, &v)| {
-                            egui_plot::Bar::new(i as f64, v as f64 / n_test as f64).width(1.0)
-                        })
+                        .map(|(i, &v)| egui_plot::Bar::new(i as f64, v).width(1.0))
                         .map(|b| {
                             let color = match b.argument as usize {
                                 ..11 => Color32::GREEN,
@@ -121,14 +213,13 @@ impl Distribution {
                     ui.vline(egui_plot::VLine::new(25.0).color(Color32::WHITE));
                 });
 
-            let rl = dist[0..=10].iter().sum::<usize>();
-            let rm = dist[11..=24].iter().sum::<usize>();
-            let rs = dist[25..].iter().sum::<usize>();
+            let rl = dist[0..=10].iter().sum::<f64>();
+            let rm = dist[11..=24].iter().sum::<f64>();
+            // rounds after `max_round` are not part of `dist`.
+            let rs = 1.0 - rl - rm;
             let text = egui::RichText::new(format!(
-                "P(X < 11) ≈ {:.2} | P(11 < X < 25) ≈ {:.2} | P(25 < X) ≈ {:.2})",
-                rl as f64 / n_test as f64,
-                rm as f64 / n_test as f64,
-                rs as f64 / n_test as f64,
+                "P(X < 11) = {:.2} | P(11 < X < 25) = {:.2} | P(25 < X) = {:.2})",
+                rl, rm, rs,
             ))
             .weak();
             ui.label(text);
@@ -136,12 +227,84 @@ impl Distribution {
     }
 }
 
+impl App {
+    /// Switches to the figures of `set`, starting a new game once the table
+    /// for them is ready.
+    fn set_figures(&mut self, set: FigureSet) {
+        if let Some(build) = self.build.take() {
+            build.cancel.store(true, Ordering::Relaxed);
+        }
+
+        self.state = new_game(&set, 0);
+        self.history = History::default();
+        self.practice = Session::default();
+        self.strategy = None;
+        self.distribution = Distribution::default();
+        self.build = Some(TableBuild::spawn(set.clone()));
+        self.figures = set;
+        self.figures_error = None;
+    }
+
+    fn figures_menu(&mut self, ui: &mut egui::Ui) {
+        ui.label("Figure set file, one `<shape> [weight]` per line.");
+        ui.text_edit_singleline(&mut self.figures_path);
+
+        ui.horizontal(|ui| {
+            if ui.button("Load").clicked() {
+                let set = FigureSet::load_from_file(&self.figures_path)
+                    .and_then(|set| set.validate(STANDARD).map(|_| set));
+                match set {
+                    Ok(set) => {
+                        self.set_figures(set);
+                        ui.close_menu();
+                    }
+                    Err(err) => self.figures_error = Some(err.to_string()),
+                }
+            }
+
+            if ui.button("Standard").clicked() {
+                self.set_figures(FigureSet::standard());
+                ui.close_menu();
+            }
+        });
+
+        if let Some(err) = &self.figures_error {
+            ui.colored_label(Color32::RED, err);
+        }
+    }
+
+    fn poll_build(&mut self, ctx: &egui::Context) {
+        let Some(build) = &self.build else {
+            return;
+        };
+
+        if !build.handle.is_finished() {
+            ctx.request_repaint_after(Duration::from_millis(100));
+            return;
+        }
+
+        let build = self.build.take().unwrap();
+        self.strategy = build.handle.join().ok().flatten();
+        if let Some(strategy) = &self.strategy {
+            self.distribution.set_state(self.state);
+            self.distribution.compute(strategy);
+        }
+    }
+}
+
 impl eframe::App for App {
     /// Called by the frame work to save state before shutdown.
-    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+    fn save(&mut self, _storage: &mut dyn eframe::Storage) {
         // eframe::set_value(storage, eframe::APP_KEY, self);
     }
 
+    /// Called once on shutdown, after `save`.
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        if let Some(build) = &self.build {
+            build.cancel.store(true, Ordering::Relaxed);
+        }
+    }
+
     /// Called each time the UI needs repainting, which may be many times per second.
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         // Put your widgets into a `SidePanel`, `TopBottomPanel`, `CentralPanel`, `Window` or `Area`.
@@ -159,75 +322,200 @@ impl eframe::App for App {
                             ctx.send_viewport_cmd(egui::ViewportCommand::Close);
                         }
                     });
+                    ui.menu_button("Figures", |ui| self.figures_menu(ui));
                     ui.add_space(16.0);
                 }
 
                 egui::widgets::global_dark_light_mode_buttons(ui);
+                ui.add_space(16.0);
+
+                ui.selectable_value(&mut self.mode, Mode::Analysis, "Analysis");
+                ui.selectable_value(&mut self.mode, Mode::Practice, "Practice");
             });
         });
 
+        self.poll_build(ctx);
+
         egui::CentralPanel::default().show(ctx, |ui| {
-            let state = self.state.clone();
+            let Some(strategy) = &self.strategy else {
+                match &self.build {
+                    Some(build) => build.ui(ui),
+                    None => {
+                        ui.label("The strategy table could not be built.");
+                    }
+                }
+                return;
+            };
+
+            let state = self.state;
 
             if self.state.has_finished() {
-                self.state = Jigsaw::default();
+                self.state = new_game(strategy.figure_set(), self.state.obstacles);
                 let mut rng = rand::SeedableRng::from_rng(rand::thread_rng()).unwrap();
-                self.state.set_random_figure(&mut rng);
+                self.state.set_random_figure(&mut rng, strategy.figures());
             }
 
-            ui.horizontal_wrapped(|ui| {
-                let text = "Configure you current game state. \
-                    You must input your board state, \
-                    current piece and number of rounds.";
-                ui.label(text);
-            });
-            ui.add_space(8.0);
+            if self.mode == Mode::Practice {
+                ui.horizontal_wrapped(|ui| {
+                    let text = "Place the current figure by clicking the cell \
+                        of its top left corner, or skip it. Every move is \
+                        graded against the strategy.";
+                    ui.label(text);
+                });
+                ui.add_space(8.0);
 
-            ui.horizontal(|ui| {
-                let best_action = self.strategy.solve(&self.state);
+                let mut action = None;
+                ui.horizontal(|ui| {
+                    action = render_practice_board(ui, &self.state);
+                    ui.add_space(8.0);
+                    render_figure(ui, &mut self.state);
+                });
+                ui.add_space(8.0);
+
+                ui.horizontal(|ui| {
+                    if ui.button("Skip").clicked() {
+                        action = Some(Action::Skip);
+                    }
+
+                    if ui.button("New game").clicked() {
+                        let before = self.state;
+                        self.state = new_game(strategy.figure_set(), self.state.obstacles);
+                        let mut rng = rand::SeedableRng::from_rng(rand::thread_rng()).unwrap();
+                        self.state.set_random_figure(&mut rng, strategy.figures());
+                        self.history.push(Turn {
+                            before,
+                            after: self.state,
+                            change: Change::Reset,
+                        });
+                    }
 
-                render_board(ui, &mut self.state, best_action);
+                    if ui.button("Reset score").clicked() {
+                        self.practice = Session::default();
+                    }
+                });
+
+                if let Some(action) = action {
+                    let values = strategy.action_values(&self.state);
+                    // the app places figures as drawn.
+                    let placement = Placement { orientation: 0, action };
+                    let grade = Grade::new(&values, placement);
+                    if let Some(grade) = grade {
+                        self.practice.record(grade);
+                    }
+                    take_turn(&mut self.state, &mut self.history, strategy, action, grade);
+                }
+
+                render_session(ui, &self.practice);
+            } else {
+                ui.horizontal_wrapped(|ui| {
+                    let text = "Configure you current game state. \
+                        You must input your board state, \
+                        current piece and number of rounds. \
+                        Right click a cell to block it.";
+                    ui.label(text);
+                });
                 ui.add_space(8.0);
-                render_figure(ui, &mut self.state);
-            });
 
-            ui.add_space(8.0);
-            ui.horizontal_wrapped(|ui| {
-                ui.label("Select the quantity of pieces you have used for your game state.");
-            });
-            ui.add(
-                egui::Slider::new(&mut self.state.round, 0..=30 as u8)
-                    .clamp_to_range(true)
-                    .text("quantity"),
-            );
+                // every change made by hand below is a turn of its own, so
+                // undoing a move never throws it away.
+                let before = self.state;
+                ui.horizontal(|ui| {
+                    let best_action = strategy.solve(&self.state);
 
-            ui.horizontal_wrapped(|ui| {
-                ui.label("Select the current figure of your game state.");
-            });
+                    render_board(ui, &mut self.state, best_action);
+                    ui.add_space(8.0);
+                    render_figure(ui, &mut self.state);
+                });
+
+                ui.add_space(8.0);
+                ui.horizontal_wrapped(|ui| {
+                    ui.label("Select the quantity of pieces you have used for your game state.");
+                });
+                ui.add(
+                    egui::Slider::new(&mut self.state.round, 0..=30u8)
+                        .clamp_to_range(true)
+                        .text("quantity"),
+                );
+
+                ui.horizontal_wrapped(|ui| {
+                    ui.label("Select the current figure of your game state.");
+                });
+
+                ui.add(
+                    egui::Slider::new(&mut self.state.figure, 0..=(self.state.figures.len() - 1) as u8)
+                        .clamp_to_range(true)
+                        .text("figure"),
+                );
+
+                if self.state != before {
+                    self.history.push(Turn {
+                        before,
+                        after: self.state,
+                        change: Change::Edit,
+                    });
+                }
+            }
 
-            ui.add(
-                egui::Slider::new(&mut self.state.figure, 0..=(FIGURES.len() - 1) as u8)
-                    .clamp_to_range(true)
-                    .text("figure"),
-            );
+            // check the most specific shortcut first, `consume_shortcut`
+            // ignores extra shift modifiers.
+            let (undo, redo) = ui.input_mut(|i| {
+                let redo = i.consume_shortcut(&REDO_SHIFT_SHORTCUT) || i.consume_shortcut(&REDO_SHORTCUT);
+                (i.consume_shortcut(&UNDO_SHORTCUT), redo)
+            });
 
             ui.horizontal(|ui| {
-                if ui.button("Take").clicked {
-                    let best_action = self.strategy.solve(&self.state);
-                    self.state.perform_action(best_action);
-                    let mut rng = rand::SeedableRng::from_rng(rand::thread_rng()).unwrap();
-                    self.state.set_random_figure(&mut rng);
-                };
+                if self.mode == Mode::Analysis {
+                    if ui.button("Take").clicked {
+                        let best_action = strategy.solve(&self.state);
+                        take_turn(&mut self.state, &mut self.history, strategy, best_action, None);
+                    };
 
-                if ui.button("Reset").clicked {
-                    self.state = Jigsaw::default();
-                };
+                    if ui.button("Reset").clicked {
+                        let before = self.state;
+                        self.state = new_game(strategy.figure_set(), self.state.obstacles);
+                        self.history.push(Turn {
+                            before,
+                            after: self.state,
+                            change: Change::Reset,
+                        });
+                    };
+
+                    ui.add_space(16.0);
+                }
+
+                let undo_button = ui
+                    .add_enabled(self.history.can_undo(), egui::Button::new("Undo"))
+                    .on_hover_text(ui.ctx().format_shortcut(&UNDO_SHORTCUT));
+                if undo_button.clicked() || undo {
+                    if let Some(turn) = self.history.undo() {
+                        self.state = turn.before;
+                        if let Change::Move { grade: Some(grade), .. } = turn.change {
+                            self.practice.undo(&grade);
+                        }
+                    }
+                }
+
+                let redo_button = ui
+                    .add_enabled(self.history.can_redo(), egui::Button::new("Redo"))
+                    .on_hover_text(ui.ctx().format_shortcut(&REDO_SHORTCUT));
+                if redo_button.clicked() || redo {
+                    if let Some(turn) = self.history.redo() {
+                        self.state = turn.after;
+                        if let Change::Move { grade: Some(grade), .. } = turn.change {
+                            self.practice.record(grade);
+                        }
+                    }
+                }
+            });
+
+            egui::CollapsingHeader::new("History").show(ui, |ui| {
+                render_history(ui, &self.history);
             });
             ui.separator();
 
             ui.horizontal_wrapped(|ui| {
-                let text = "This plot represents a distribution of \
-                    scores computed by simulating your current \
+                let text = "This plot represents the exact distribution of \
+                    scores obtained by playing your current \
                     game state until the game has finished following \
                     the strategy. You can see the probability of \
                     finishing the game in less than 10 rounds below.";
@@ -238,9 +526,10 @@ impl eframe::App for App {
             if self.state.round != state.round
                 || self.state.figure != state.figure
                 || self.state.board != state.board
+                || self.state.obstacles != state.obstacles
             {
                 self.distribution.set_state(self.state);
-                self.distribution.compute(&self.strategy);
+                self.distribution.compute(strategy);
             }
             ui.horizontal(|ui| {
                 ui.allocate_ui(egui::Vec2::new(ui.available_width(), 100.0), |ui| {
@@ -248,41 +537,46 @@ impl eframe::App for App {
                 });
             });
 
-            ui.separator();
+            // the ranking of every action would give the practice answers away.
+            if self.mode == Mode::Analysis {
+                ui.separator();
 
-            let text = "The plot below represents the average amount \
-                of pieces you will need to use from the current \
-                game state to the end of the game taking a specific action.\
-                Therefore, the smaller the value, the better the outcome will be.";
-            ui.label(text);
+                let text = "The plot below represents the average amount \
+                    of pieces you will need to use from the current \
+                    game state to the end of the game taking a specific action.\
+                    Therefore, the smaller the value, the better the outcome will be.";
+                ui.label(text);
 
-            ui.allocate_ui(egui::Vec2::new(ui.available_width(), 140.0), |ui| {
-                egui_plot::Plot::new("bar-chart")
-                    .allow_zoom(false)
-                    .allow_drag(false)
-                    .allow_scroll(false)
-                    .allow_boxed_zoom(false)
-                    .allow_double_click_reset(false)
-                    .show_grid(false)
-                    .include_x(0.0)
-                    .show(ui, |ui| {
-                        let mut bars = vec![];
-                        for action in self.state.legal_actions() {
-                            let mut s = self.state.clone();
-                            s.perform_action(action);
-                            let dst: Vec<(u8, f32)> = self.strategy.distances(s.board).collect();
-
-                            let n = dst.len();
-                            let sum = dst.iter().map(|(_, b)| *b).sum::<f32>();
-                            let avg = sum as f64 / n as f64;
-
-                            let bar = egui_plot::Bar::new(action as f64, avg).width(1.0);
-                            bars.push(bar);
-                        }
-                        let plt = egui_plot::BarChart::new(bars);
-                        ui.bar_chart(plt);
-                    });
-            });
+                let values = strategy.action_values(&self.state);
+                if let [(_, best), (_, second), ..] = values[..] {
+                    ui.label(format!(
+                        "The best action needs {:.3} fewer pieces on average than the second best one.",
+                        second - best
+                    ));
+                }
+
+                ui.allocate_ui(egui::Vec2::new(ui.available_width(), 140.0), |ui| {
+                    egui_plot::Plot::new("bar-chart")
+                        .allow_zoom(false)
+                        .allow_drag(false)
+                        .allow_scroll(false)
+                        .allow_boxed_zoom(false)
+                        .allow_double_click_reset(false)
+                        .show_grid(false)
+                        .include_x(0.0)
+                        .show(ui, |ui| {
+                            let bars = values
+                                .iter()
+                                .filter(|(_, value)| value.is_finite())
+                                .map(|&(placement, value)| {
+                                    egui_plot::Bar::new(placement.action.index(self.state.dims) as f64, value as f64).width(1.0)
+                                })
+                                .collect();
+                            let plt = egui_plot::BarChart::new(bars);
+                            ui.bar_chart(plt);
+                        });
+                });
+            }
             ui.add_space(16.0);
 
             ui.with_layout(egui::Layout::bottom_up(egui::Align::LEFT), |ui| {
@@ -299,17 +593,16 @@ fn render_figure(ui: &mut egui::Ui, state: &mut Jigsaw) {
     ui.vertical(|ui| {
         ui.spacing_mut().item_spacing = (0.0, 0.0).into();
 
-        let (max_x, max_y) = (3, 3);
-
-        for i in 0..max_x {
-            let mut mask = 1 << (jigsaw::N * jigsaw::M - (i + 1));
+        // same square for every figure so the layout does not jump around.
+        let span = state.figures.iter().map(|f: &Figure| f.width.max(f.height)).max().unwrap();
 
+        for y in 0..span {
             ui.horizontal(|ui| {
-                for _ in 0..max_y {
+                for x in 0..span {
                     let (rect, _response) =
                         ui.allocate_exact_size(cell_size.into(), egui::Sense::hover());
 
-                    let value = state.figure().value & mask != 0;
+                    let value = state.figure().contains((x, y));
 
                     let color = if value {
                         egui::Color32::RED
@@ -320,23 +613,191 @@ fn render_figure(ui: &mut egui::Ui, state: &mut Jigsaw) {
                     ui.painter().rect_filled(rect, 0.0, color);
                     ui.painter()
                         .rect_stroke(rect, 0.0, (1.0, egui::Color32::WHITE));
+                }
+            });
+        }
+    });
+}
 
-                    mask >>= jigsaw::N;
+/// Empty standard board drawing the figures of `set`, blocked by
+/// `obstacles` so new games keep the layout of the event board.
+fn new_game(set: &FigureSet, obstacles: u32) -> Jigsaw {
+    Jigsaw {
+        obstacles,
+        figures: set.figures(),
+        ..Default::default()
+    }
+}
+
+fn paint_obstacle(ui: &egui::Ui, rect: egui::Rect) {
+    let stroke = (2.0, egui::Color32::GRAY);
+    ui.painter().rect_filled(rect, 0.0, egui::Color32::DARK_GRAY);
+    ui.painter().line_segment([rect.left_top(), rect.right_bottom()], stroke);
+    ui.painter().line_segment([rect.right_top(), rect.left_bottom()], stroke);
+}
+
+/// Plays `action`, draws the next figure and records the turn together with
+/// the action the strategy recommended and the practice `grade` of the move.
+fn take_turn(state: &mut Jigsaw, history: &mut History, strategy: &Deterministic, action: Action, grade: Option<Grade>) {
+    let before = *state;
+    let recommended = strategy.solve(state);
+
+    state.perform_action(action);
+    let mut rng = rand::SeedableRng::from_rng(rand::thread_rng()).unwrap();
+    state.set_random_figure(&mut rng, strategy.figures());
+
+    history.push(Turn {
+        before,
+        after: *state,
+        change: Change::Move {
+            action,
+            recommended,
+            grade,
+        },
+    });
+}
+
+fn render_session(ui: &mut egui::Ui, session: &Session) {
+    if let Some(grade) = session.last() {
+        let text = if grade.is_optimal() {
+            format!("You took {}, a best move.", format_action(grade.placement.action))
+        } else {
+            format!(
+                "You took {}, the best move was {}. Loss: {:.2} pieces.",
+                format_action(grade.placement.action),
+                format_action(grade.best.action),
+                grade.loss(),
+            )
+        };
+        let color = if grade.is_optimal() { Color32::GREEN } else { Color32::RED };
+        ui.label(egui::RichText::new(text).color(color));
+    }
+
+    let text = format!(
+        "Moves: {} | Accuracy: {:.0}% | Total regret: {:.2} pieces",
+        session.moves(),
+        session.accuracy().unwrap_or(0.0) * 100.0,
+        session.regret(),
+    );
+    ui.label(egui::RichText::new(text).weak());
+}
+
+fn format_action(action: Action) -> String {
+    match action.offsets() {
+        Some(offsets) => format!("{:?}", offsets),
+        None => "skip".to_string(),
+    }
+}
+
+fn render_history(ui: &mut egui::Ui, history: &History) {
+    if history.turns().is_empty() {
+        ui.label(egui::RichText::new("No turns yet.").weak());
+        return;
+    }
+
+    egui::ScrollArea::vertical()
+        .max_height(96.0)
+        .stick_to_bottom(true)
+        .show(ui, |ui| {
+            for turn in history.turns() {
+                let text = match turn.change {
+                    Change::Move {
+                        action,
+                        recommended,
+                        ..
+                    } => format!(
+                        "Round {}: figure {}, took {}, recommended {}",
+                        turn.before.round,
+                        turn.before.figure,
+                        format_action(action),
+                        format_action(recommended),
+                    ),
+                    Change::Edit => "Edit".to_string(),
+                    Change::Reset => "Reset".to_string(),
+                };
+
+                let color = match turn.followed() {
+                    Some(true) => Color32::GREEN,
+                    Some(false) => Color32::RED,
+                    None => ui.visuals().weak_text_color(),
+                };
+                ui.label(egui::RichText::new(text).color(color));
+            }
+        });
+}
+
+/// Board where the current figure is placed by clicking the cell of its top
+/// left corner, returning the clicked action when it is legal.
+fn render_practice_board(ui: &mut egui::Ui, state: &Jigsaw) -> Option<Action> {
+    let cell_size = (30.0, 30.0);
+    let mut rects = vec![];
+    let mut hovered = None;
+    let mut clicked = None;
+
+    ui.vertical(|ui| {
+        ui.spacing_mut().item_spacing = (0.0, 0.0).into();
+
+        for y_offset in 0..state.dims.rows {
+            ui.horizontal(|ui| {
+                for x_offset in 0..state.dims.columns {
+                    let offsets = (x_offset, y_offset);
+                    let action = Action::from(offsets);
+
+                    let (rect, response) =
+                        ui.allocate_exact_size(cell_size.into(), egui::Sense::click());
+                    rects.push((offsets, rect));
+
+                    if response.hovered() {
+                        hovered = Some(action);
+                    }
+                    if response.clicked() && state.is_legal(action) {
+                        clicked = Some(action);
+                    }
                 }
             });
         }
     });
+
+    for (offsets, rect) in rects {
+        if state.is_obstacle(offsets) {
+            paint_obstacle(ui, rect);
+        } else if state.get_value(offsets) {
+            ui.painter().rect_filled(rect, 0.0, egui::Color32::GOLD);
+        }
+
+        if let Some(action @ Action::Place { x, y }) = hovered {
+            // placements past `max_offset` would wrap around the board.
+            let fits = state
+                .figure()
+                .max_offset(state.dims)
+                .is_some_and(|max_offset| x <= max_offset.0 && y <= max_offset.1);
+
+            if fits && state.fig_intesect(action, offsets) {
+                let color = if state.is_legal(action) {
+                    egui::Color32::GREEN.gamma_multiply(0.5)
+                } else {
+                    egui::Color32::RED.gamma_multiply(0.5)
+                };
+                ui.painter().rect_filled(rect, 0.0, color);
+            }
+        }
+
+        ui.painter()
+            .rect_stroke(rect, 0.0, (1.0, egui::Color32::WHITE));
+    }
+
+    clicked
 }
 
-fn render_board(ui: &mut egui::Ui, state: &mut Jigsaw, best_action: u8) {
+fn render_board(ui: &mut egui::Ui, state: &mut Jigsaw, best_action: Action) {
     let cell_size = (30.0, 30.0);
 
     ui.vertical(|ui| {
         ui.spacing_mut().item_spacing = (0.0, 0.0).into();
 
-        for y_offset in 0..jigsaw::N {
+        for y_offset in 0..state.dims.rows {
             ui.horizontal(|ui| {
-                for x_offset in 0..jigsaw::M {
+                for x_offset in 0..state.dims.columns {
                     let offsets = (x_offset, y_offset);
 
                     let (rect, response) =
@@ -345,7 +806,9 @@ fn render_board(ui: &mut egui::Ui, state: &mut Jigsaw, best_action: u8) {
                     ui.painter()
                         .rect_stroke(rect, 0.0, (1.0, egui::Color32::WHITE));
 
-                    if state.get_value(offsets) {
+                    if state.is_obstacle(offsets) {
+                        paint_obstacle(ui, rect);
+                    } else if state.get_value(offsets) {
                         ui.painter().rect_filled(rect, 0.0, egui::Color32::GOLD);
                     }
 
@@ -357,7 +820,7 @@ fn render_board(ui: &mut egui::Ui, state: &mut Jigsaw, best_action: u8) {
                         ui.painter().text(
                             rect.center(),
                             egui::Align2::CENTER_CENTER,
-                            format!("{}", Jigsaw::offset_to_action(offsets)),
+                            format!("{}", Action::from(offsets).index(state.dims)),
                             egui::FontId::default(),
                             egui::Color32::WHITE,
                         );
@@ -366,6 +829,9 @@ fn render_board(ui: &mut egui::Ui, state: &mut Jigsaw, best_action: u8) {
                     if response.clicked() {
                         state.toggle(offsets);
                     }
+                    if response.secondary_clicked() {
+                        state.toggle_obstacle(offsets);
+                    }
                 }
             });
         }
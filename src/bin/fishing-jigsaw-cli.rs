@@ -0,0 +1,675 @@
+use std::collections::HashMap;
+use std::process::ExitCode;
+use std::time::Duration;
+
+use fishing_jigsaw::deterministic::{Deterministic, MAX_TABLE_CELLS};
+use fishing_jigsaw::distribution::round_distribution;
+use fishing_jigsaw::figure_set::FigureSet;
+use fishing_jigsaw::heuristic::{EdgeHugging, FirstLegal, Greedy, RandomLegal};
+use fishing_jigsaw::jigsaw::{Dimensions, FigureDistribution, Jigsaw, Orientations, STANDARD};
+use fishing_jigsaw::mcts::{Budget, Mcts};
+use fishing_jigsaw::response::SolverResponse;
+use fishing_jigsaw::solver::Solver;
+use fishing_jigsaw::tiered::{Tiered, TIERS};
+use fishing_jigsaw::tournament::{Report, Tournament};
+
+const USAGE: &str = "\
+usage: fishing-jigsaw-cli <command> [options]
+
+commands:
+    solve       print the recommended action for a game state, as documented on
+                `SolverResponse`, with the value of every action for the table
+    simulate    print the distribution of the final round for a game state
+    table       build the policy table and save it to a file
+    compare     play several solvers on the same figures and compare their results
+
+options:
+    --state <notation>     game state as `##..../....../....../...... <figure> <round>`
+    --board <u32>          board bitmask, decimal, 0x or 0b prefixed (default 0)
+    --obstacles <u32>      bitmask of blocked cells, same format as `--board` (default 0)
+    --figure <index>       current figure, index into the figure set (default 0)
+    --round <u8>           pieces used so far (default 0)
+    --rows <u8>            board rows, with `--board` or for `table` (default 4)
+    --columns <u8>         board columns, with `--board` or for `table` (default 6)
+    --figures <path>       figure set file, one `<shape> [weight]` per line (default FIGURES)
+    --weights <w,...>      figure drop weights, overriding the figure set ones
+    --orientations <rule>  fixed, rotations or all to also mirror figures (default fixed)
+    --solver <name>        deterministic, tiered, mcts or one of the baselines random,
+                           first-legal, greedy and edge-hugging (default deterministic)
+    --iterations <n>       playouts per move of the mcts solver (default 10000)
+    --time <ms>            search time per move of the mcts solver, instead of `--iterations`
+    --seed <u64>           seed of the mcts and random solvers (default 0)
+    --max-round <u8>       last round of the distribution (simulate), or of a game (compare, default 64)
+    --solvers <name,...>   solvers to compare, as named by `--solver` (default deterministic,greedy)
+    --games <n>            games played by each solver (compare, default 1000)
+    --format <format>      json or text (compare, default json)
+    --table <path>         load a table saved by `table` instead of building it
+    --output <path>        destination file (table)
+
+Results are printed to stdout as a single JSON object, unless `--format text` is given.
+`simulate` only follows games until `--max-round`: `covered` is the probability of
+finishing by then, `mean` the mean final round of those games, and tiers past
+`--max-round` are null.";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    let result = match args.split_first() {
+        Some((command, rest)) => parse_options(rest).and_then(|opts| match command.as_str() {
+            "solve" => solve(&opts),
+            "simulate" => simulate(&opts),
+            "table" => table(&opts),
+            "compare" => compare(&opts),
+            _ => Err(format!("unknown command `{}`", command)),
+        }),
+        None => Err("missing command".to_string()),
+    };
+
+    match result {
+        Ok(json) => {
+            println!("{}", json);
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            eprintln!("error: {}\n\n{}", err, USAGE);
+            ExitCode::from(2)
+        }
+    }
+}
+
+type Options = HashMap<String, String>;
+
+fn parse_options(args: &[String]) -> Result<Options, String> {
+    let mut opts = Options::new();
+    let mut iter = args.iter();
+
+    while let Some(arg) = iter.next() {
+        let key = arg
+            .strip_prefix("--")
+            .ok_or_else(|| format!("unexpected argument `{}`", arg))?;
+        let value = iter
+            .next()
+            .ok_or_else(|| format!("missing value for `--{}`", key))?;
+        opts.insert(key.to_string(), value.clone());
+    }
+
+    Ok(opts)
+}
+
+fn parse_u32(value: &str) -> Result<u32, String> {
+    let parsed = if let Some(hex) = value.strip_prefix("0x") {
+        u32::from_str_radix(hex, 16)
+    } else if let Some(bin) = value.strip_prefix("0b") {
+        u32::from_str_radix(&bin.replace('_', ""), 2)
+    } else {
+        value.parse()
+    };
+
+    parsed.map_err(|err| format!("invalid number `{}`: {}", value, err))
+}
+
+fn parse_u8(opts: &Options, key: &str, default: u8) -> Result<u8, String> {
+    match opts.get(key) {
+        Some(value) => value
+            .parse()
+            .map_err(|err| format!("invalid `--{}` value `{}`: {}", key, value, err)),
+        None => Ok(default),
+    }
+}
+
+fn parse_game(opts: &Options, set: &FigureSet) -> Result<Jigsaw, String> {
+    let mut game = parse_state(opts, set)?;
+    set.validate(game.dims).map_err(|err| err.to_string())?;
+    game.orientations = parse_orientations(opts)?;
+    Ok(game)
+}
+
+fn parse_orientations(opts: &Options) -> Result<Orientations, String> {
+    match opts.get("orientations").map(String::as_str) {
+        None | Some("fixed") => Ok(Orientations::Fixed),
+        Some("rotations") => Ok(Orientations::Rotations),
+        Some("all") => Ok(Orientations::RotationsAndReflections),
+        Some(value) => Err(format!("unknown orientation rule `{}`", value)),
+    }
+}
+
+fn parse_state(opts: &Options, set: &FigureSet) -> Result<Jigsaw, String> {
+    if let Some(value) = opts.get("state") {
+        if ["board", "obstacles", "figure", "round", "rows", "columns"].iter().any(|key| opts.contains_key(*key)) {
+            return Err("`--state` cannot be combined with `--board`, `--obstacles`, `--figure`, `--round`, `--rows` or `--columns`".to_string());
+        }
+        return Jigsaw::parse_with(value, set.figures())
+            .map_err(|err| format!("invalid `--state` value `{}`: {}", value, err));
+    }
+
+    let dims = parse_dims(opts)?;
+    let board = match opts.get("board") {
+        Some(value) => parse_u32(value)?,
+        None => 0,
+    };
+    if board > dims.terminal_state::<u32>() {
+        return Err(format!("board `{:#x}` has more than {} cells", board, dims.cells()));
+    }
+    let obstacles = match opts.get("obstacles") {
+        Some(value) => parse_u32(value)?,
+        None => 0,
+    };
+    if obstacles > dims.terminal_state::<u32>() {
+        return Err(format!("obstacles `{:#x}` have more than {} cells", obstacles, dims.cells()));
+    }
+    if board & obstacles != 0 {
+        return Err("`--board` and `--obstacles` overlap".to_string());
+    }
+
+    let figure = parse_u8(opts, "figure", 0)?;
+    if figure as usize >= set.figures().len() {
+        return Err(format!("figure must be lower than {}", set.figures().len()));
+    }
+
+    Ok(Jigsaw {
+        board,
+        obstacles,
+        figure,
+        round: parse_u8(opts, "round", 0)?,
+        dims,
+        figures: set.figures(),
+        orientations: Orientations::Fixed,
+    })
+}
+
+fn parse_dims(opts: &Options) -> Result<Dimensions, String> {
+    let rows = parse_u8(opts, "rows", STANDARD.rows)?;
+    let columns = parse_u8(opts, "columns", STANDARD.columns)?;
+    // the solvers index their tables by `u32` boards.
+    if !Dimensions::is_valid(rows, columns) || !Dimensions::new(rows, columns).fits::<u32>() {
+        return Err(format!("a {}x{} board must have between 1 and {} cells", rows, columns, u32::BITS));
+    }
+
+    Ok(Dimensions::new(rows, columns))
+}
+
+// tables need an entry per board, the other solvers play any board.
+fn check_table_dims(dims: Dimensions) -> Result<(), String> {
+    if dims.cells() > MAX_TABLE_CELLS {
+        return Err(format!(
+            "a {}x{} board has more than the {} cells a table can solve, use `--solver mcts` or a baseline",
+            dims.rows, dims.columns, MAX_TABLE_CELLS
+        ));
+    }
+
+    Ok(())
+}
+
+fn parse_figures(opts: &Options) -> Result<FigureSet, String> {
+    let set = match opts.get("figures") {
+        Some(path) => FigureSet::load_from_file(path)
+            .map_err(|err| format!("cannot load `{}`: {}", path, err))?,
+        None => FigureSet::standard(),
+    };
+
+    let Some(value) = opts.get("weights") else {
+        return Ok(set);
+    };
+
+    let weights = value
+        .split(',')
+        .map(|w| w.trim().parse::<f32>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|err| format!("invalid `--weights` value `{}`: {}", value, err))?;
+
+    if weights.len() != set.figures().len() {
+        return Err(format!("expected {} weights", set.figures().len()));
+    }
+    if !FigureDistribution::is_valid(&weights) {
+        return Err("weights must be non-negative and not all zero".to_string());
+    }
+
+    Ok(set.with_distribution(FigureDistribution::new(&weights)))
+}
+
+// a loaded table brings its own weights, so the distribution the solver was
+// built for is returned alongside it.
+fn build_solver(opts: &Options, game: &Jigsaw, set: FigureSet) -> Result<(Box<dyn Solver>, FigureDistribution), String> {
+    let name = opts.get("solver").map_or("deterministic", String::as_str);
+    build_named_solver(name, opts, game, set)
+}
+
+fn build_named_solver(
+    name: &str,
+    opts: &Options,
+    game: &Jigsaw,
+    set: FigureSet,
+) -> Result<(Box<dyn Solver>, FigureDistribution), String> {
+    match name {
+        "deterministic" => {
+            let strategy = build_table(opts, game, set)?;
+            let figures = strategy.figures().clone();
+            Ok((Box::new(strategy), figures))
+        }
+        "tiered" if !game.orientations.is_fixed() => {
+            Err("the tiered solver does not turn figures, use `--orientations fixed`".to_string())
+        }
+        "tiered" => {
+            check_table_dims(game.dims)?;
+            let figures = set.distribution().clone();
+            let mut solver = Tiered::with_figures(game.dims, &TIERS, set).map_err(|err| err.to_string())?;
+            solver.run_parallel();
+            Ok((Box::new(solver), figures))
+        }
+        "mcts" => {
+            let figures = set.distribution().clone();
+            let solver = Mcts::new(figures.clone())
+                .with_budget(parse_budget(opts)?)
+                .with_seed(parse_seed(opts)?);
+            Ok((Box::new(solver), figures))
+        }
+        "random" => Ok((Box::new(RandomLegal::new(parse_seed(opts)?)), set.distribution().clone())),
+        "first-legal" => Ok((Box::new(FirstLegal), set.distribution().clone())),
+        "greedy" => Ok((Box::new(Greedy), set.distribution().clone())),
+        "edge-hugging" => Ok((Box::new(EdgeHugging), set.distribution().clone())),
+        _ => Err(format!("unknown solver `{}`", name)),
+    }
+}
+
+fn parse_seed(opts: &Options) -> Result<u64, String> {
+    match opts.get("seed") {
+        Some(value) => value
+            .parse()
+            .map_err(|err| format!("invalid `--seed` value `{}`: {}", value, err)),
+        None => Ok(0),
+    }
+}
+
+fn parse_budget(opts: &Options) -> Result<Budget, String> {
+    match (opts.get("iterations"), opts.get("time")) {
+        (Some(_), Some(_)) => Err("`--iterations` cannot be combined with `--time`".to_string()),
+        (Some(value), None) => match value.parse() {
+            Ok(0) => Err("`--iterations` must be positive".to_string()),
+            Ok(n) => Ok(Budget::Iterations(n)),
+            Err(err) => Err(format!("invalid `--iterations` value `{}`: {}", value, err)),
+        },
+        (None, Some(value)) => value
+            .parse()
+            .map(|ms| Budget::Time(Duration::from_millis(ms)))
+            .map_err(|err| format!("invalid `--time` value `{}`: {}", value, err)),
+        (None, None) => Ok(Budget::default()),
+    }
+}
+
+fn build_table(opts: &Options, game: &Jigsaw, set: FigureSet) -> Result<Deterministic, String> {
+    check_table_dims(game.dims)?;
+    let Some(path) = opts.get("table") else {
+        let mut strategy = Deterministic::with_orientations(game.dims, set, game.orientations);
+        strategy.run_parallel();
+        return Ok(strategy);
+    };
+
+    let strategy = Deterministic::load_from_file(path)
+        .map_err(|err| format!("cannot load `{}`: {}", path, err))?;
+    let given = opts.contains_key("figures") || opts.contains_key("weights");
+    if given && strategy.figure_set() != &set {
+        return Err(format!("`{}` was built for different figures or weights", path));
+    }
+    if strategy.figure_set().figures() != game.figures {
+        return Err(format!("`{}` was built for different figures, pass them with `--figures`", path));
+    }
+    if strategy.dims() != game.dims {
+        return Err(format!("`{}` was built for a different board size", path));
+    }
+    if strategy.orientations() != game.orientations {
+        return Err(format!("`{}` was built for a different `--orientations` rule", path));
+    }
+
+    Ok(strategy)
+}
+
+// `value` as a JSON string, quotes included.
+fn json_string(value: &str) -> String {
+    let mut json = String::with_capacity(value.len() + 2);
+    json.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if c.is_control() => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+fn json_f64(value: f64) -> String {
+    if value.is_finite() {
+        format!("{}", value)
+    } else {
+        "null".to_string()
+    }
+}
+
+fn solve(opts: &Options) -> Result<String, String> {
+    let set = parse_figures(opts)?;
+    let game = parse_game(opts, &set)?;
+
+    // only the table values every action, the other solvers leave `values`
+    // empty.
+    let response = match opts.get("solver").map_or("deterministic", String::as_str) {
+        "deterministic" => SolverResponse::new(&build_table(opts, &game, set)?, &game),
+        _ => {
+            let (solver, _) = build_solver(opts, &game, set)?;
+            let placement = solver.placement(&game);
+            SolverResponse {
+                state: game,
+                action: placement.action,
+                orientation: placement.orientation,
+                values: Vec::new(),
+            }
+        }
+    };
+
+    Ok(response_json(&response))
+}
+
+// same layout as `SolverResponse` serialized with the `serde` feature, so
+// tools parse a single schema.
+fn response_json(response: &SolverResponse) -> String {
+    let state = response.state;
+    let offsets = |offsets: Option<(u8, u8)>| match offsets {
+        Some((x, y)) => format!("[{},{}]", x, y),
+        None => "null".to_string(),
+    };
+
+    let mut fields = vec![format!("\"board\":{}", state.board)];
+    if state.obstacles != 0 {
+        fields.push(format!("\"obstacles\":{}", state.obstacles));
+    }
+    fields.push(format!("\"figure\":{},\"round\":{}", state.figure, state.round));
+    fields.push(format!(
+        "\"dims\":{{\"rows\":{},\"columns\":{}}}",
+        state.dims.rows, state.dims.columns
+    ));
+    if !state.orientations.is_fixed() {
+        fields.push(format!("\"orientations\":{}", json_string(&format!("{:?}", state.orientations))));
+    }
+
+    let values = response
+        .values
+        .iter()
+        .map(|v| {
+            format!(
+                "{{\"action\":{},\"orientation\":{},\"expected_pieces\":{}}}",
+                offsets(v.action.offsets()),
+                v.orientation,
+                v.expected_pieces.map_or("null".to_string(), |e| format!("{:?}", e))
+            )
+        })
+        .collect::<Vec<_>>();
+
+    format!(
+        "{{\"state\":{{{}}},\"action\":{},\"orientation\":{},\"values\":[{}]}}",
+        fields.join(","),
+        offsets(response.action.offsets()),
+        response.orientation,
+        values.join(",")
+    )
+}
+
+fn simulate(opts: &Options) -> Result<String, String> {
+    let set = parse_figures(opts)?;
+    let game = parse_game(opts, &set)?;
+    let max_round = parse_u8(opts, "max-round", 64)?;
+    let (solver, figures) = build_solver(opts, &game, set)?;
+
+    // games still going after `max_round` are missing from the pmf, so the
+    // mean only covers the finished ones and later tiers are unknown.
+    let pmf = round_distribution(solver.as_ref(), &game, &figures, max_round);
+    let covered = pmf.iter().sum::<f64>();
+    let mean = pmf.iter().enumerate().map(|(r, p)| r as f64 * p).sum::<f64>() / covered;
+    let tiers = TIERS
+        .iter()
+        .map(|&t| {
+            if t <= max_round {
+                json_f64(pmf[..=t as usize].iter().sum::<f64>())
+            } else {
+                "null".to_string()
+            }
+        })
+        .collect::<Vec<_>>();
+
+    Ok(format!(
+        "{{\"mean\":{},\"covered\":{},\"tiers\":[{}],\"pmf\":[{}]}}",
+        json_f64(mean),
+        json_f64(covered),
+        tiers.join(","),
+        pmf.iter().map(|&p| json_f64(p)).collect::<Vec<_>>().join(",")
+    ))
+}
+
+fn table(opts: &Options) -> Result<String, String> {
+    let path = opts.get("output").ok_or("missing `--output`")?;
+    let dims = parse_dims(opts)?;
+    check_table_dims(dims)?;
+    let set = parse_figures(opts)?;
+    set.validate(dims).map_err(|err| err.to_string())?;
+    let mut strategy = Deterministic::with_orientations(dims, set, parse_orientations(opts)?);
+    strategy.run_parallel();
+    strategy
+        .save_to_file(path)
+        .map_err(|err| format!("cannot write `{}`: {}", path, err))?;
+
+    Ok(format!(
+        "{{\"output\":{},\"boards\":{},\"bytes\":{}}}",
+        json_string(path),
+        strategy.boards(),
+        strategy.memory()
+    ))
+}
+
+fn compare(opts: &Options) -> Result<String, String> {
+    let set = parse_figures(opts)?;
+    let game = parse_game(opts, &set)?;
+    let names = opts.get("solvers").map_or("deterministic,greedy", String::as_str);
+    let games = match opts.get("games") {
+        Some(value) => match value.parse() {
+            Ok(0) => return Err("`--games` must be positive".to_string()),
+            Ok(n) => n,
+            Err(err) => return Err(format!("invalid `--games` value `{}`: {}", value, err)),
+        },
+        None => 1000,
+    };
+    let max_round = parse_u8(opts, "max-round", 64)?;
+    if max_round == u8::MAX {
+        return Err(format!("`--max-round` must be lower than {}", u8::MAX));
+    }
+
+    let solvers = names
+        .split(',')
+        .map(|name| {
+            let name = name.trim();
+            build_named_solver(name, opts, &game, set.clone()).map(|(solver, figures)| (name, solver, figures))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // a loaded table brings its own weights, which are the ones the figures
+    // are drawn with.
+    let figures = solvers
+        .iter()
+        .map(|(_, _, figures)| figures)
+        .find(|&figures| figures != set.distribution())
+        .unwrap_or(set.distribution());
+    let named = solvers
+        .iter()
+        .map(|(name, solver, _)| (*name, solver.as_ref()))
+        .collect::<Vec<_>>();
+    let report = Tournament::default()
+        .with_games(games)
+        .with_seed(parse_seed(opts)?)
+        .with_max_round(max_round)
+        .run(&game, figures, &named);
+
+    match opts.get("format").map(String::as_str) {
+        None | Some("json") => Ok(report_json(&report)),
+        Some("text") => Ok(report.to_string().trim_end().to_string()),
+        Some(value) => Err(format!("unknown format `{}`", value)),
+    }
+}
+
+fn report_json(report: &Report) -> String {
+    let list = |values: Vec<String>| values.join(",");
+    let standings = report
+        .standings
+        .iter()
+        .map(|s| {
+            format!(
+                "{{\"name\":{},\"mean\":{},\"median\":{},\"percentiles\":[{}],\"tiers\":[{}],\"unfinished\":{}}}",
+                json_string(&s.name),
+                json_f64(s.mean),
+                s.median,
+                list(s.percentiles.iter().map(|p| format!("{{\"percentile\":{},\"round\":{}}}", p.percentile, p.round)).collect()),
+                list(s.tiers.iter().map(|&t| json_f64(t)).collect()),
+                json_f64(s.unfinished)
+            )
+        })
+        .collect();
+    let wins = report
+        .wins
+        .iter()
+        .map(|row| format!("[{}]", list(row.iter().map(|&w| json_f64(w)).collect())))
+        .collect();
+
+    format!(
+        "{{\"games\":{},\"tiers\":[{}],\"standings\":[{}],\"wins\":[{}]}}",
+        report.games,
+        list(report.tiers.iter().map(u8::to_string).collect()),
+        list(standings),
+        list(wins)
+    )
+}
+
+#[cfg(test)]
+mod cli_test {
+    use super::*;
+    use std::fs;
+    use std::path::PathBuf;
+
+    fn options(pairs: &[(&str, &str)]) -> Options {
+        pairs.iter().map(|&(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    // directory of the files a test writes, holding a figure set that fits a
+    // 2x2 board.
+    fn scratch(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("fishing-jigsaw-cli-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("figures.txt"), "#\n##\n##/##\n").unwrap();
+        dir
+    }
+
+    fn parse(output: &str) -> serde_json::Value {
+        serde_json::from_str(output).unwrap()
+    }
+
+    #[test]
+    fn test_json_string() {
+        let value = "a\"b\\c\nd\te\u{1}f\u{7f}";
+        let json = json_string(value);
+        assert_eq!(serde_json::from_str::<String>(&json).unwrap(), value);
+    }
+
+    #[test]
+    fn test_table_output() {
+        let dir = scratch("table");
+        let output = dir.join("line\nbreak.table");
+        let figures = dir.join("figures.txt");
+        let opts = options(&[
+            ("output", output.to_str().unwrap()),
+            ("figures", figures.to_str().unwrap()),
+            ("rows", "2"),
+            ("columns", "2"),
+        ]);
+
+        let json = parse(&table(&opts).unwrap());
+        assert_eq!(json["output"], output.to_str().unwrap());
+        assert!(json["boards"].as_u64().unwrap() > 0);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_simulate_output() {
+        let dir = scratch("simulate");
+        let figures = dir.join("figures.txt");
+        let opts = |max_round| {
+            options(&[
+                ("figures", figures.to_str().unwrap()),
+                ("rows", "2"),
+                ("columns", "2"),
+                ("board", "0"),
+                ("max-round", max_round),
+            ])
+        };
+
+        // both tiers end after the last round followed.
+        let json = parse(&simulate(&opts("5")).unwrap());
+        assert!(json["tiers"].as_array().unwrap().iter().all(|t| t.is_null()));
+        let covered = json["covered"].as_f64().unwrap();
+        assert!(covered > 0.0 && covered < 1.0);
+        assert_eq!(json["pmf"].as_array().unwrap().len(), 6);
+
+        let json = parse(&simulate(&opts("12")).unwrap());
+        assert!(json["tiers"][0].as_f64().unwrap() > covered);
+        assert!(json["tiers"][1].is_null());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_solve_output() {
+        let dir = scratch("solve");
+        let figures = dir.join("figures.txt");
+        let mut opts = options(&[
+            ("figures", figures.to_str().unwrap()),
+            ("rows", "2"),
+            ("columns", "2"),
+            ("board", "1"),
+            ("figure", "1"),
+        ]);
+
+        let json = parse(&solve(&opts).unwrap());
+        assert_eq!(json["state"]["board"], 1);
+        assert!(json["action"].is_array());
+        assert!(!json["values"].as_array().unwrap().is_empty());
+
+        opts.insert("solver".to_string(), "greedy".to_string());
+        let json = parse(&solve(&opts).unwrap());
+        assert!(json["values"].as_array().unwrap().is_empty());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    // the command line writes the same JSON the library serializes.
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_solve_matches_response() {
+        let dir = scratch("response");
+        let figures = dir.join("figures.txt");
+        let opts = options(&[
+            ("figures", figures.to_str().unwrap()),
+            ("rows", "2"),
+            ("columns", "2"),
+            ("board", "1"),
+            ("obstacles", "2"),
+            ("figure", "0"),
+            ("orientations", "rotations"),
+        ]);
+
+        let set = parse_figures(&opts).unwrap();
+        let game = parse_game(&opts, &set).unwrap();
+        let mut strategy = Deterministic::with_orientations(game.dims, set, game.orientations);
+        strategy.run();
+        let expected = serde_json::to_string(&SolverResponse::new(&strategy, &game)).unwrap();
+
+        assert_eq!(parse(&solve(&opts).unwrap()), parse(&expected));
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
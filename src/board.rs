@@ -0,0 +1,68 @@
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Not, Shr};
+
+/// Bitset of board cells. Cells are stored column by column from the most
+/// significant cell of the board, so a board of `cells` cells uses the
+/// `cells` lowest bits and placing a figure is a right shift by the action.
+///
+/// Implemented for `u32`, `u64` and `u128`, the wider ones being needed for
+/// boards of more than 32 cells.
+pub trait Board:
+    Copy
+    + Eq
+    + Ord
+    + Hash
+    + Debug
+    + Default
+    + Send
+    + Sync
+    + 'static
+    + BitAnd<Output = Self>
+    + BitOr<Output = Self>
+    + BitXor<Output = Self>
+    + Not<Output = Self>
+    + BitAndAssign
+    + BitOrAssign
+    + BitXorAssign
+    + Shr<u8, Output = Self>
+{
+    const BITS: u32;
+    const EMPTY: Self;
+
+    /// Board with only bit `index` set, counting from the least significant.
+    fn bit(index: u8) -> Self;
+
+    /// Board with the `cells` lowest bits set.
+    fn full(cells: u8) -> Self;
+
+    fn count_ones(self) -> u32;
+}
+
+macro_rules! impl_board {
+    ($($t:ty),*) => {
+        $(
+            impl Board for $t {
+                const BITS: u32 = <$t>::BITS;
+                const EMPTY: Self = 0;
+
+                #[inline(always)]
+                fn bit(index: u8) -> Self {
+                    1 << index
+                }
+
+                #[inline(always)]
+                fn full(cells: u8) -> Self {
+                    <$t>::MAX >> (<$t>::BITS - cells as u32)
+                }
+
+                #[inline(always)]
+                fn count_ones(self) -> u32 {
+                    <$t>::count_ones(self)
+                }
+            }
+        )*
+    };
+}
+
+impl_board!(u32, u64, u128);
@@ -1,160 +1,1036 @@
-use std::collections::VecDeque;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::mem;
+use std::ops::ControlFlow;
+use std::path::Path;
 
-use lazy_static::lazy_static;
-use crate::jigsaw::{Figure, Jigsaw, FIGURES, N, SKIP_ACTION, TERMINAL_STATE, TOTAL_CELLS, TOTAL_FIGURES};
+use crate::figure_set::FigureSet;
+use crate::layers::{self, Layered};
+use crate::symmetry::Symmetries;
+use crate::jigsaw::{Action, Dimensions, Figure, FigureDistribution, Jigsaw, Orientations, Placement, STANDARD};
 use itertools::Itertools;
 
-#[derive(Clone, Copy, Debug)]
-struct State {
-    dsts: [f32; TOTAL_FIGURES],
-    actions: [u8; TOTAL_FIGURES],
-    in_stack: bool,
+// best expected distance when skipping: waiting for any figure of a set
+// costs `(1 + sum(p * d)) / sum(p)` over the figures of the set, and the
+// best set is always made of the figures with the lowest distances, so only
+// those sets are tried. `order` is scratch space for the sort.
+#[inline(always)]
+fn skip_distance(dsts: &[f32], probabilities: &[f32], order: &mut Vec<usize>) -> f32 {
+    order.clear();
+    order.extend((0..dsts.len()).filter(|&f| probabilities[f] > 0.0));
+    order.sort_unstable_by(|&a, &b| dsts[a].total_cmp(&dsts[b]));
+
+    let (mut p, mut sum) = (0.0, 0.0);
+    order
+        .iter()
+        .map(|&f| {
+            p += probabilities[f];
+            sum += probabilities[f] * dsts[f];
+            // expected rolls to get the set + avg distance
+            (1.0 + sum) / p
+        })
+        .fold(f32::INFINITY, f32::min)
 }
 
-impl State {
-    fn new() -> Self {
-        Self {
-            dsts: [f32::INFINITY; TOTAL_FIGURES],
-            actions: [SKIP_ACTION; TOTAL_FIGURES],
-            in_stack: false,
-        }
+#[inline(always)]
+fn avg_dst(dsts: &[f32], probabilities: &[f32]) -> f32 {
+    dsts.iter()
+        .zip(probabilities)
+        .map(|(&d, &p)| weighted(p, d))
+        .sum::<f32>()
+}
+
+// figures that are never drawn must not turn an infinite distance into NaN.
+#[inline(always)]
+fn weighted(p: f32, dst: f32) -> f32 {
+    if p == 0.0 { 0.0 } else { p * dst }
+}
+
+// table files start with `TABLE_MAGIC`, `TABLE_VERSION` (u32), the number of
+// rows, columns and figures, the orientation rule (see `orientations_byte`)
+// and whether mirror boards are stored once (u8 each), the figure shapes
+// (u64 each, see `Figure::shape`), the figure probabilities (f32 each), then
+// one record per stored board with its actions (u8 each), orientations (u8
+// each, only when figures may be turned) and distances (f32 each), and end
+// with the FNV-1a hash (u64) of everything before it. All numbers are little
+// endian.
+const TABLE_MAGIC: &[u8; 4] = b"FJTB";
+const TABLE_VERSION: u32 = 5;
+const HEADER_SIZE: usize = 13;
+
+fn orientations_byte(orientations: Orientations) -> u8 {
+    match orientations {
+        Orientations::Fixed => 0,
+        Orientations::Rotations => 1,
+        Orientations::RotationsAndReflections => 2,
     }
+}
 
-    #[inline(always)]
-    fn compute_none_dst(&mut self) {
-        lazy_static! {
-            static ref SETS: Vec<Vec<usize>> = (0..TOTAL_FIGURES).powerset().skip(1).collect();
-        };
+fn orientations_from_byte(byte: u8) -> Option<Orientations> {
+    match byte {
+        0 => Some(Orientations::Fixed),
+        1 => Some(Orientations::Rotations),
+        2 => Some(Orientations::RotationsAndReflections),
+        _ => None,
+    }
+}
 
-        let skp_dst = SETS
-            .iter()
-            .map(|subset| {
-                let sum = subset.iter().map(|&idx| self.dsts[idx]).sum::<f32>();
-                let len = subset.len();
-                // expected rolls to get the set + avg distance
-                (TOTAL_FIGURES as f32 + sum) / len as f32
-            })
-            .reduce(f32::min)
-            .unwrap();
+struct Fnv(u64);
 
-        self.actions
-            .iter_mut()
-            .zip(self.dsts.iter_mut())
-            .filter(|(_, &mut d)| d > skp_dst)
-            .for_each(|(action, value)| {
-                *action = SKIP_ACTION;
-                *value = skp_dst;
-            });
+impl Fnv {
+    fn new() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
     }
 
-    #[inline(always)]
-    fn avg_dst(&self) -> f32 {
-        self.dsts.iter().sum::<f32>() / TOTAL_FIGURES as f32
+    fn update(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.0 ^= b as u64;
+            self.0 = self.0.wrapping_mul(0x0000_0100_0000_01b3);
+        }
     }
 }
 
-pub struct Deterministic {
-    arr: Vec<State>,
+/// Most cells a board solved by a table can have. Tables hold an entry per
+/// board, so every cell more doubles their size: the 5x5 event board already
+/// needs about a gigabyte before symmetries are taken into account, and a 4x8
+/// board would need over a hundred.
+pub const MAX_TABLE_CELLS: u8 = 25;
+
+/// Error returned when a table is asked for a board with more than
+/// `MAX_TABLE_CELLS` cells.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TooManyCells(pub Dimensions);
+
+impl fmt::Display for TooManyCells {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "a {}x{} board has more than the {} cells a table can solve",
+            self.0.rows, self.0.columns, MAX_TABLE_CELLS
+        )
+    }
 }
 
-#[inline(always)]
-fn is_possible(board: u32, figure: &Figure, x_offset: u8, y_offset: u8) -> bool {
-    // 000CCC|RR
-    let mut ilegal = false;
+impl std::error::Error for TooManyCells {}
 
-    ilegal |= x_offset > figure.max_offset.0;
-    ilegal |= y_offset > figure.max_offset.1;
-    ilegal |= (board | figure.value >> (x_offset * N + y_offset)) != board;
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.to_string())
+}
 
-    return !ilegal;
+/// Policy minimizing the expected number of pieces needed to fill the board.
+/// The table holds, for every board and figure of the set, the best action
+/// and the expected number of pieces left after it, stored board by board.
+///
+/// When figures may be turned the table also holds the orientation of each
+/// action, an index into `Orientations::variants`, see `Solver::placement`.
+///
+/// Boards that are mirror images of each other, with the figure set mapped
+/// onto itself, play the same, so only the smallest board of each group is
+/// stored and queries are mirrored back and forth. On the standard board the
+/// half turn swaps the two L shaped figures, halving the table.
+pub struct Deterministic {
+    dsts: Vec<f32>,
+    actions: Vec<u8>,
+    turns: Vec<u8>,
+    set: FigureSet,
+    dims: Dimensions,
+    orientations: Orientations,
+    symmetries: Symmetries,
+    // every orientation of every figure that fits the board, with its index
+    // in `Orientations::variants`, the figure placed at `(0, 0)` and its
+    // maximum offsets.
+    placements: Vec<(usize, u8, u32, (u8, u8))>,
+}
+
+impl Default for Deterministic {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Deterministic {
     pub fn new() -> Self {
+        Self::with_distribution(FigureDistribution::default())
+    }
+
+    pub fn with_distribution(figures: FigureDistribution) -> Self {
+        Self::with_dimensions(STANDARD, figures)
+    }
+
+    pub fn with_dimensions(dims: Dimensions, figures: FigureDistribution) -> Self {
+        Self::with_figures(dims, FigureSet::standard().with_distribution(figures))
+    }
+
+    /// Strategy for boards of `dims` drawing the figures of `set`. The table
+    /// holds one entry per board so it needs `2^cells` of them, and boards
+    /// with more than `MAX_TABLE_CELLS` cells are refused.
+    pub fn with_figures(dims: Dimensions, set: FigureSet) -> Self {
+        Self::with_orientations(dims, set, Orientations::Fixed)
+    }
+
+    /// Same as `with_figures` for games where figures may be turned as
+    /// `orientations` allows before placing them.
+    pub fn with_orientations(dims: Dimensions, set: FigureSet, orientations: Orientations) -> Self {
+        Self::build(dims, set, orientations, true)
+    }
+
+    /// Same as `with_orientations` storing every board instead of a single
+    /// one out of each group of mirror boards, mostly useful to check the
+    /// reduced table against.
+    pub fn unreduced(dims: Dimensions, set: FigureSet, orientations: Orientations) -> Self {
+        Self::build(dims, set, orientations, false)
+    }
+
+    fn build(dims: Dimensions, set: FigureSet, orientations: Orientations, reduce: bool) -> Self {
+        assert!(dims.cells() <= MAX_TABLE_CELLS, "{} cells need too large a table", dims.cells());
+        let symmetries = Symmetries::new(dims, &set, orientations, reduce);
+        let entries = symmetries.boards() * set.figures().len();
+        let turns = if orientations.is_fixed() { 0 } else { entries };
+        let placements = set
+            .figures()
+            .iter()
+            .enumerate()
+            .flat_map(|(f_idx, f)| {
+                orientations
+                    .variants(f)
+                    .into_iter()
+                    .enumerate()
+                    .filter_map(move |(turn, v)| {
+                        Some((f_idx, turn as u8, v.value::<u32>(dims), v.max_offset(dims)?))
+                    })
+            })
+            .collect();
+
         Self {
-            arr: vec![State::new(); 1 << TOTAL_CELLS],
+            dsts: vec![f32::INFINITY; entries],
+            actions: vec![dims.skip_action(); entries],
+            turns: vec![0; turns],
+            set,
+            dims,
+            orientations,
+            symmetries,
+            placements,
         }
     }
 
+    pub fn figures(&self) -> &FigureDistribution {
+        self.set.distribution()
+    }
+
+    pub fn figure_set(&self) -> &FigureSet {
+        &self.set
+    }
+
+    pub fn dims(&self) -> Dimensions {
+        self.dims
+    }
+
+    pub fn orientations(&self) -> Orientations {
+        self.orientations
+    }
+
+    /// Number of boards stored in the table, out of `2^cells`.
+    pub fn boards(&self) -> usize {
+        self.symmetries.boards()
+    }
+
+    /// Bytes used by the table. Building it also needs the results of one
+    /// height layer at a time, a fifth of the table on the standard board.
+    pub fn memory(&self) -> usize {
+        self.dsts.len() * mem::size_of::<f32>() + self.actions.len() + self.turns.len() + self.symmetries.bytes()
+    }
+
+    /// Bytes a table for `dims`, `set` and `orientations` would use, see
+    /// `memory`, without allocating it.
+    pub fn estimate_memory(dims: Dimensions, set: &FigureSet, orientations: Orientations) -> usize {
+        assert!(dims.cells() <= MAX_TABLE_CELLS, "{} cells need too large a table", dims.cells());
+        let symmetries = Symmetries::new(dims, set, orientations, true);
+        let turns = if orientations.is_fixed() { 0 } else { 1 };
+        let entry = mem::size_of::<f32>() + 1 + turns;
+
+        symmetries.boards() * set.figures().len() * entry + symmetries.bytes()
+    }
+
     pub fn run(&mut self) {
-        let mut stacks = VecDeque::new();
-
-        for _ in 0..=TOTAL_CELLS as usize {
-            // could be less memory but it does not really matter.
-            let stack = Vec::with_capacity(1 << TOTAL_CELLS);
-            stacks.push_back(stack);
-        }
-
-        stacks[0].push(TERMINAL_STATE);
-        self.arr[TERMINAL_STATE as usize].in_stack = true;
-        self.arr[TERMINAL_STATE as usize].dsts = [0.0; TOTAL_FIGURES];
-        self.arr[TERMINAL_STATE as usize].actions = [SKIP_ACTION; TOTAL_FIGURES];
-
-        let actions = (0..TOTAL_CELLS as u8).map(|a| (a >> 2, a & 0b11)).collect_vec();
-
-        // the algorithm could be implemented in parallel, but the communication
-        // and synchronization overhead between threads has a heavy impact. 
-        for height in 0..TOTAL_CELLS {
-            let valid_figures = FIGURES
-                .iter()
-                .filter(|&f| height + f.size <= TOTAL_CELLS)
-                .enumerate().collect_vec();
-
-            while let Some(board) = stacks[height as usize].pop() {
-                self.arr[board as usize].compute_none_dst();
-                let dst = 1.0 + self.arr[board as usize].avg_dst();
-
-                for (f_idx, f) in &valid_figures {
-                    for (x, y) in actions.iter()
-                        .filter(|&(x, y)| is_possible(board, f, *x, *y))
-                    {
-                        let new_board = board & !(f.value >> (x * N + y));
-                       
-                        if dst < self.arr[new_board as usize].dsts[*f_idx] {
-                            self.arr[new_board as usize].dsts[*f_idx] = dst;
-                            self.arr[new_board as usize].actions[*f_idx] = (x << 2) | y;
-                        }
-
-                        if !self.arr[new_board as usize].in_stack {
-                            self.arr[new_board as usize].in_stack = true;
-
-                            stacks[(height + f.size) as usize].push(new_board);
-                        }
-                    }
-                }
+        let _ = self.run_with_progress(|_| ControlFlow::Continue(()));
+    }
+
+    /// Same as `run`, calling `progress` with the number of height layers
+    /// processed so far, out of the number of cells, after each one of them.
+    /// Returning `ControlFlow::Break` cancels the build and leaves the table
+    /// incomplete, which is reported back as `ControlFlow::Break` too.
+    pub fn run_with_progress<F>(&mut self, progress: F) -> ControlFlow<()>
+    where
+        F: FnMut(u8) -> ControlFlow<()>,
+    {
+        self.run_layers(false, progress)
+    }
+
+    /// Same as `run`, solving the boards of each height layer on every core.
+    /// The table is the same one `run` builds, bit for bit.
+    pub fn run_parallel(&mut self) {
+        let _ = self.run_parallel_with_progress(|_| ControlFlow::Continue(()));
+    }
+
+    /// Same as `run_with_progress`, in parallel like `run_parallel`.
+    pub fn run_parallel_with_progress<F>(&mut self, progress: F) -> ControlFlow<()>
+    where
+        F: FnMut(u8) -> ControlFlow<()>,
+    {
+        self.run_layers(true, progress)
+    }
+
+    // boards pull the distances of the boards each placement leads to, see
+    // `layers::run_layers`.
+    fn run_layers<F>(&mut self, parallel: bool, progress: F) -> ControlFlow<()>
+    where
+        F: FnMut(u8) -> ControlFlow<()>,
+    {
+        let dims = self.dims;
+        let k = self.set.figures().len();
+
+        // every symmetry keeps the full board, so it is canonical.
+        let terminal_index = self.symmetries.index(dims.terminal_state::<u32>());
+        self.dsts[terminal_index * k..][..k].fill(0.0);
+        self.actions[terminal_index * k..][..k].fill(dims.skip_action());
+
+        layers::run_layers(self, parallel, progress)
+    }
+
+    /// Best action and expected pieces left after it for each figure of the
+    /// set when it is drawn on `board`.
+    pub fn distances(&self, board: u32) -> impl Iterator<Item = (Action, f32)> + '_ {
+        (0..self.set.figures().len()).map(move |figure| {
+            let (placement, dst) = self.lookup(board, figure);
+            (placement.action, dst)
+        })
+    }
+
+    // table entry of `figure` drawn on `board`, mirrored back from the
+    // canonical board when needed.
+    fn lookup(&self, board: u32, figure: usize) -> (Placement, f32) {
+        let k = self.set.figures().len();
+        let (canonical, symmetry) = self.symmetries.canonical(board);
+        let mapped = symmetry.map_or(figure, |symmetry| symmetry.figure_index(figure));
+
+        let entry = self.symmetries.index(canonical) * k + mapped;
+        let mut orientation = self.turns.get(entry).copied().unwrap_or(0);
+        let mut action = self.actions[entry];
+        let dst = self.dsts[entry];
+
+        if let Some(symmetry) = symmetry.filter(|_| action != self.dims.skip_action()) {
+            // mirroring keeps the bounding box of the figure.
+            let variant = self.orientations.variants(&self.set.figures()[mapped])[orientation as usize];
+            let max_offset = variant.max_offset(self.dims).unwrap();
+            orientation = symmetry.inverse_turn(mapped, orientation);
+            action = symmetry.action(action, max_offset, self.dims);
+        }
+
+        let placement = Placement {
+            orientation,
+            action: Action::from_index(action, self.dims).unwrap(),
+        };
+        (placement, dst)
+    }
+
+    /// Expected number of pieces left to finish `game`, counting the current
+    /// one, infinite when it cannot be finished. Obstacles need no pieces, so
+    /// only the cells left to fill are counted, see `Jigsaw::empty_cells`.
+    pub fn expected_pieces(&self, game: &Jigsaw) -> f32 {
+        assert_eq!(game.dims, self.dims);
+        if game.has_finished() {
+            return 0.0;
+        }
+
+        self.lookup(game.occupied(), game.figure as usize).1
+    }
+
+    /// Every legal placement of the current figure of `game`, skipping
+    /// included, with the expected number of pieces left to finish the game
+    /// after it, counting the current one, best first. Placements after which
+    /// the board cannot be finished are worth `f32::INFINITY`, and ties keep
+    /// the order of `Jigsaw::legal_placements`. Finished games have none.
+    pub fn action_values(&self, game: &Jigsaw) -> Vec<(Placement, f32)> {
+        assert_eq!(game.dims, self.dims);
+        assert_eq!(game.orientations, self.orientations);
+        if game.has_finished() {
+            return Vec::new();
+        }
+
+        let probabilities = self.set.distribution().probabilities();
+        let mut dsts = Vec::with_capacity(probabilities.len());
+        let mut values = game
+            .legal_placements()
+            .into_iter()
+            .map(|placement| {
+                let mut next = *game;
+                next.perform_placement(placement);
+                dsts.clear();
+                dsts.extend(self.distances(next.occupied()).map(|(_, d)| d));
+                (placement, 1.0 + avg_dst(&dsts, probabilities))
+            })
+            .collect_vec();
+
+        values.sort_by(|a, b| a.1.total_cmp(&b.1));
+        values
+    }
+
+    pub fn save<W: Write>(&self, writer: W) -> io::Result<()> {
+        let mut writer = BufWriter::new(writer);
+        let mut hash = Fnv::new();
+        let figures = self.set.figures();
+
+        let mut header = TABLE_MAGIC.to_vec();
+        header.extend(TABLE_VERSION.to_le_bytes());
+        header.extend([
+            self.dims.rows,
+            self.dims.columns,
+            figures.len() as u8,
+            orientations_byte(self.orientations),
+            self.symmetries.is_reduced() as u8,
+        ]);
+        header.extend(figures.iter().flat_map(|f| f.shape.to_le_bytes()));
+        header.extend(self.figures().probabilities().iter().flat_map(|p| p.to_le_bytes()));
+        hash.update(&header);
+        writer.write_all(&header)?;
+
+        let k = figures.len();
+        let mut record = Vec::with_capacity(k * 6);
+        for (board, (actions, dsts)) in self.actions.chunks_exact(k).zip(self.dsts.chunks_exact(k)).enumerate() {
+            record.clear();
+            record.extend(actions);
+            if !self.orientations.is_fixed() {
+                record.extend(&self.turns[board * k..][..k]);
             }
+            record.extend(dsts.iter().flat_map(|d| d.to_le_bytes()));
+            hash.update(&record);
+            writer.write_all(&record)?;
         }
+
+        writer.write_all(&hash.0.to_le_bytes())?;
+        writer.flush()
     }
 
-    /* pub fn solve(&mut self, game: &Jigsaw) -> u8 {
-        let (f_idx, _) = FIGURES
+    pub fn load<R: Read>(reader: R) -> io::Result<Self> {
+        let mut reader = BufReader::new(reader);
+        let mut hash = Fnv::new();
+
+        let mut header = [0u8; HEADER_SIZE];
+        reader.read_exact(&mut header)?;
+        hash.update(&header);
+
+        if &header[0..4] != TABLE_MAGIC {
+            return Err(invalid_data("not a fishing jigsaw table"));
+        }
+        if header[4..8] != TABLE_VERSION.to_le_bytes() {
+            return Err(invalid_data("unsupported table version"));
+        }
+        let (rows, columns, k) = (header[8], header[9], header[10] as usize);
+        if !Dimensions::is_valid(rows, columns) || k == 0 {
+            return Err(invalid_data("table was built for a different board"));
+        }
+        let dims = Dimensions::new(rows, columns);
+        if dims.cells() > MAX_TABLE_CELLS {
+            return Err(invalid_data("table was built for a different board"));
+        }
+        let orientations = orientations_from_byte(header[11])
+            .ok_or_else(|| invalid_data("unknown orientation rule"))?;
+        let reduce = match header[12] {
+            0 => false,
+            1 => true,
+            _ => return Err(invalid_data("invalid table layout")),
+        };
+
+        let mut figures = vec![0u8; k * 12];
+        reader.read_exact(&mut figures)?;
+        hash.update(&figures);
+        let (shapes, probabilities) = figures.split_at(k * 8);
+
+        let shapes = shapes
+            .chunks_exact(8)
+            .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
+            .collect_vec();
+        // shapes are stored already moved to the top left corner.
+        if shapes.iter().any(|&shape| shape == 0 || Figure::from_shape(shape).shape != shape) {
+            return Err(invalid_data("invalid figure shape"));
+        }
+        let probabilities = probabilities
+            .chunks_exact(4)
+            .map(|bytes| f32::from_le_bytes(bytes.try_into().unwrap()))
+            .collect_vec();
+        // tables are always saved with normalized probabilities.
+        let total = probabilities.iter().sum::<f32>();
+        if !FigureDistribution::is_valid(&probabilities) || (total - 1.0).abs() > 1e-4 {
+            return Err(invalid_data("invalid figure probabilities"));
+        }
+
+        let distribution = FigureDistribution::from_probabilities(probabilities);
+        let figures = shapes.into_iter().map(Figure::from_shape).collect_vec();
+        let set = FigureSet::new(figures, distribution);
+
+        let mut strategy = Self::build(dims, set, orientations, reduce);
+        if strategy.symmetries.is_reduced() != reduce {
+            return Err(invalid_data("invalid table layout"));
+        }
+        let turns = if orientations.is_fixed() { 0 } else { k };
+        // maximum offsets of every orientation of every figure, `None` when
+        // it does not fit, to check the records against.
+        let bounds = strategy
+            .set
+            .figures()
             .iter()
+            .map(|f| orientations.variants(f).iter().map(|v| v.max_offset(dims)).collect_vec())
+            .collect_vec();
+        let is_valid = |figure: usize, action: u8, turn: u8| match bounds[figure].get(turn as usize) {
+            _ if action == dims.skip_action() => turn == 0,
+            Some(&Some((max_x, max_y))) if action < dims.skip_action() => {
+                let (x, y) = dims.action_to_offsets(action);
+                x <= max_x && y <= max_y
+            }
+            _ => false,
+        };
+
+        let mut record = vec![0u8; k * 5 + turns];
+        for (board, (actions, dsts)) in strategy
+            .actions
+            .chunks_exact_mut(k)
+            .zip(strategy.dsts.chunks_exact_mut(k))
             .enumerate()
-            .find(|(_, f)| f.value == game.figure().value)
-            .unwrap();
+        {
+            reader.read_exact(&mut record)?;
+            hash.update(&record);
+
+            let (action_bytes, rest) = record.split_at(k);
+            let (turn_bytes, dst_bytes) = rest.split_at(turns);
+            for (figure, &action) in action_bytes.iter().enumerate() {
+                let turn = turn_bytes.get(figure).copied().unwrap_or(0);
+                if !is_valid(figure, action, turn) {
+                    return Err(invalid_data("invalid action in table"));
+                }
+            }
+            actions.copy_from_slice(action_bytes);
+            if turns > 0 {
+                strategy.turns[board * k..][..k].copy_from_slice(turn_bytes);
+            }
+            for (d, bytes) in dsts.iter_mut().zip(dst_bytes.chunks_exact(4)) {
+                *d = f32::from_le_bytes(bytes.try_into().unwrap());
+            }
+        }
+
+        let mut checksum = [0u8; 8];
+        reader.read_exact(&mut checksum)?;
+        if u64::from_le_bytes(checksum) != hash.0 {
+            return Err(invalid_data("table checksum mismatch"));
+        }
+        if reader.read(&mut [0u8; 1])? != 0 {
+            return Err(invalid_data("unexpected data after table"));
+        }
+
+        Ok(strategy)
+    }
 
-        let action = self.arr[game.board as usize].actions[f_idx];
-        action
-    } */
+    pub fn save_to_file<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        self.save(File::create(path)?)
+    }
 
-    pub fn distances(&self, board: u32) -> impl Iterator<Item = (u8, f32)> {
-        let state = &self.arr[board as usize];
-        state.actions.into_iter().zip(state.dsts).clone()
+    pub fn load_from_file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::load(File::open(path)?)
     }
 }
 
 use crate::solver::Solver;
 
 impl Solver for Deterministic {
-    fn solve(&self, game: &Jigsaw) -> u8 {
-        let (f_idx, _) = FIGURES
-            .iter()
-            .enumerate()
-            .find(|(_, f)| f.value == game.figure().value)
-            .unwrap();
+    // the table of turning figures only holds placements of turned variants,
+    // which are not legal actions for the figure as drawn.
+    fn solve(&self, game: &Jigsaw) -> Action {
+        assert!(
+            game.orientations.is_fixed(),
+            "figures may be turned, use `Solver::placement` instead of `Solver::solve`"
+        );
+        self.placement(game).action
+    }
 
-        let action = self.arr[game.board as usize].actions[f_idx];
-        action
+    fn placement(&self, game: &Jigsaw) -> Placement {
+        assert_eq!(game.dims, self.dims);
+        assert_eq!(game.orientations, self.orientations);
+        debug_assert_eq!(game.figures, self.set.figures());
+
+        self.lookup(game.occupied(), game.figure as usize).0
     }
 }
 
+impl Layered for Deterministic {
+    // distance, action and orientation of each figure.
+    type Entry = (f32, u8, u8);
+    // distances and order of the figures, see `skip_distance`.
+    type Scratch = (Vec<f32>, Vec<usize>);
+
+    fn symmetries(&self) -> &Symmetries {
+        &self.symmetries
+    }
+
+    fn cells(&self) -> u8 {
+        self.dims.cells()
+    }
+
+    fn width(&self) -> usize {
+        self.set.figures().len()
+    }
+
+    // best placement of every figure on `board`, trying every orientation of
+    // every figure.
+    fn solve_board(&self, board: u32, (dsts, order): &mut Self::Scratch, row: &mut [(f32, u8, u8)]) {
+        let dims = self.dims;
+        let k = row.len();
+        let probabilities = self.set.distribution().probabilities();
+
+        row.fill((f32::INFINITY, dims.skip_action(), 0));
+
+        // placements are tried in action order, so the first of the best
+        // ones is kept whatever the order boards are solved in.
+        for &(f_idx, turn, value, max_offset) in &self.placements {
+            for x in 0..=max_offset.0 {
+                for y in 0..=max_offset.1 {
+                    let action = dims.offset_to_action((x, y));
+                    let figure = value >> action;
+                    if board & figure != 0 {
+                        continue;
+                    }
+
+                    // mirror boards share their distances, which do not
+                    // depend on the figures being mapped onto each other.
+                    let (next, _) = self.symmetries.canonical(board | figure);
+                    let entry = self.symmetries.index(next) * k;
+                    let dst = 1.0 + avg_dst(&self.dsts[entry..][..k], probabilities);
+
+                    if dst < row[f_idx].0 {
+                        row[f_idx] = (dst, action, turn);
+                    }
+                }
+            }
+        }
+
+        dsts.clear();
+        dsts.extend(row.iter().map(|&(d, _, _)| d));
+        let skp_dst = skip_distance(dsts, probabilities, order);
+        for entry in row.iter_mut() {
+            if entry.0 > skp_dst {
+                *entry = (skp_dst, dims.skip_action(), 0);
+            }
+        }
+    }
+
+    fn store(&mut self, board: u32, row: &[(f32, u8, u8)]) {
+        let k = row.len();
+        let entry = self.symmetries.index(board) * k;
+        for (i, &(dst, action, turn)) in row.iter().enumerate() {
+            self.dsts[entry + i] = dst;
+            self.actions[entry + i] = action;
+            if !self.turns.is_empty() {
+                self.turns[entry + i] = turn;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod deterministic_test {
+    use super::*;
+    use crate::jigsaw::FIGURES;
+
+    fn header() -> Vec<u8> {
+        let mut header = TABLE_MAGIC.to_vec();
+        header.extend(TABLE_VERSION.to_le_bytes());
+        header.extend([STANDARD.rows, STANDARD.columns, FIGURES.len() as u8, 0, 1]);
+        header.extend(FIGURES.iter().flat_map(|f| f.shape.to_le_bytes()));
+        header.extend(FigureDistribution::default().probabilities().iter().flat_map(|p| p.to_le_bytes()));
+        header
+    }
+
+    fn load_error(bytes: &[u8]) -> io::ErrorKind {
+        Deterministic::load(bytes).err().unwrap().kind()
+    }
+
+    #[test]
+    fn test_load_rejects_header() {
+        let mut bytes = header();
+        bytes[0] = b'X';
+        assert_eq!(load_error(&bytes), io::ErrorKind::InvalidData);
+
+        let mut bytes = header();
+        bytes[4] = TABLE_VERSION as u8 + 1;
+        assert_eq!(load_error(&bytes), io::ErrorKind::InvalidData);
+
+        let mut bytes = header();
+        bytes[8] = 8;
+        assert_eq!(load_error(&bytes), io::ErrorKind::InvalidData);
+
+        let mut bytes = header();
+        bytes[10] = 0;
+        assert_eq!(load_error(&bytes), io::ErrorKind::InvalidData);
+
+        let mut bytes = header();
+        bytes[11] = 3;
+        assert_eq!(load_error(&bytes), io::ErrorKind::InvalidData);
+
+        let mut bytes = header();
+        bytes[12] = 2;
+        assert_eq!(load_error(&bytes), io::ErrorKind::InvalidData);
+
+        // the single cell figure moved away from the top left corner.
+        let mut bytes = header();
+        bytes[13] = 0b10;
+        assert_eq!(load_error(&bytes), io::ErrorKind::InvalidData);
+
+        // valid weights that do not add up to one.
+        let mut bytes = header();
+        let probabilities = HEADER_SIZE + FIGURES.len() * 8;
+        for p in bytes[probabilities..].chunks_exact_mut(4) {
+            p.copy_from_slice(&1.0f32.to_le_bytes());
+        }
+        assert_eq!(load_error(&bytes), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_load_rejects_truncated() {
+        assert_eq!(load_error(&header()[..6]), io::ErrorKind::UnexpectedEof);
+        assert_eq!(load_error(&header()), io::ErrorKind::UnexpectedEof);
+    }
+
+    // `bytes` of a saved table with the checksum recomputed after editing.
+    fn rehash(mut bytes: Vec<u8>) -> Vec<u8> {
+        let end = bytes.len() - 8;
+        let mut hash = Fnv::new();
+        hash.update(&bytes[..end]);
+        bytes[end..].copy_from_slice(&hash.0.to_le_bytes());
+        bytes
+    }
+
+    #[test]
+    fn test_load_rejects_records() {
+        let dims = Dimensions::new(2, 2);
+        let set = FigureSet::standard();
+        let records = HEADER_SIZE + set.figures().len() * 12;
+
+        let mut strategy = Deterministic::with_figures(dims, set.clone());
+        strategy.run();
+        let mut bytes = Vec::new();
+        strategy.save(&mut bytes).unwrap();
+        assert!(Deterministic::load(rehash(bytes.clone()).as_slice()).is_ok());
+
+        bytes[records] = dims.skip_action() + 1;
+        assert_eq!(load_error(&rehash(bytes)), io::ErrorKind::InvalidData);
+
+        // the long piece never fits a 2x2 board, so it can only be skipped.
+        let mut bytes = Vec::new();
+        strategy.save(&mut bytes).unwrap();
+        bytes[records + 1] = 0;
+        assert_eq!(load_error(&rehash(bytes)), io::ErrorKind::InvalidData);
+
+        // the single cell figure has a single orientation.
+        let mut strategy = Deterministic::with_orientations(dims, set, Orientations::Rotations);
+        strategy.run();
+        let mut bytes = Vec::new();
+        strategy.save(&mut bytes).unwrap();
+        bytes[records + 6] = 1;
+        assert_eq!(load_error(&rehash(bytes)), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_load_rejects_corruption() {
+        let dims = Dimensions::new(2, 2);
+        let set = FigureSet::standard();
+        let records = HEADER_SIZE + set.figures().len() * 12;
+
+        let mut strategy = Deterministic::with_figures(dims, set.clone());
+        strategy.run();
+        let mut bytes = Vec::new();
+        strategy.save(&mut bytes).unwrap();
+
+        // a distance is still a valid number, only the checksum catches it.
+        let mut corrupted = bytes.clone();
+        corrupted[records + set.figures().len()] ^= 1;
+        let err = Deterministic::load(corrupted.as_slice()).err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(err.to_string(), "table checksum mismatch");
+
+        bytes.push(0);
+        let err = Deterministic::load(bytes.as_slice()).err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(err.to_string(), "unexpected data after table");
+    }
+
+    #[test]
+    fn test_small_board() {
+        let dims = Dimensions::new(2, 2);
+        let mut strategy = Deterministic::with_dimensions(dims, FigureDistribution::default());
+        strategy.run();
+
+        // the square fills the whole board at once.
+        let mut game = Jigsaw::new(dims);
+        game.figure = 4;
+        assert_eq!(strategy.solve(&game), Action::Place { x: 0, y: 0 });
+        assert_eq!(strategy.distances(0).nth(4), Some((Action::Place { x: 0, y: 0 }, 1.0)));
+
+        // the long piece never fits, so it is always skipped.
+        game.figure = 1;
+        assert_eq!(strategy.solve(&game), Action::Skip);
+
+        let mut bytes = Vec::new();
+        strategy.save(&mut bytes).unwrap();
+        let loaded = Deterministic::load(bytes.as_slice()).unwrap();
+        assert_eq!(loaded.dims(), dims);
+        assert_eq!(loaded.distances(0).collect_vec(), strategy.distances(0).collect_vec());
+    }
+
+    #[test]
+    fn test_custom_figures() {
+        let dims = Dimensions::new(2, 3);
+        let set: FigureSet = "###\n#/# 3".parse().unwrap();
+        let mut strategy = Deterministic::with_figures(dims, set.clone());
+        strategy.run();
+
+        let mut game = Jigsaw::new(dims);
+        game.figures = set.figures();
+        game.figure = 0;
+        // the bar only fits at the start of a row.
+        let action = strategy.solve(&game);
+        assert_eq!(action.offsets().map(|(x, _)| x), Some(0));
+
+        let mut bytes = Vec::new();
+        strategy.save(&mut bytes).unwrap();
+        let loaded = Deterministic::load(bytes.as_slice()).unwrap();
+        assert_eq!(loaded.figure_set(), &set);
+        assert_eq!(loaded.solve(&game), action);
+    }
+
+    #[test]
+    fn test_weighted_figures() {
+        let dims = Dimensions::new(2, 3);
+        // some figures are never drawn, and their infinite distances on
+        // boards they do not fit must not spoil the averages.
+        let figures = FigureDistribution::new(&[3.0, 0.0, 1.0, 2.0, 0.5, 1.0]);
+        let mut strategy = Deterministic::with_dimensions(dims, figures.clone());
+        strategy.run();
+
+        for figure in 0..FIGURES.len() as u8 {
+            let game = Jigsaw { figure, ..Jigsaw::new(dims) };
+            let expected = strategy.expected_pieces(&game);
+            assert!(expected.is_finite());
+
+            let pmf = crate::distribution::round_distribution(&strategy, &game, &figures, 200);
+            assert!((pmf.iter().sum::<f64>() - 1.0).abs() < 1e-6);
+            let mean = pmf.iter().enumerate().map(|(r, p)| r as f64 * p).sum::<f64>();
+            assert!((mean as f32 - expected).abs() < 1e-4 * expected);
+        }
+
+        // without the single cell figure the last hole is never filled.
+        let figures = FigureDistribution::new(&[0.0, 1.0, 1.0, 1.0, 1.0, 1.0]);
+        let mut strategy = Deterministic::with_dimensions(dims, figures);
+        strategy.run();
+        let game: Jigsaw = "#../### 2 0".parse().unwrap();
+        assert_eq!(strategy.expected_pieces(&game), f32::INFINITY);
+        assert!(strategy.action_values(&game).iter().all(|&(_, v)| v == f32::INFINITY));
+    }
+
+    // expected pieces left playing `placement` and then following `strategy`.
+    fn value(strategy: &Deterministic, game: &Jigsaw, placement: Placement) -> f32 {
+        let probabilities = strategy.figures().probabilities();
+        if placement.action.is_skip() {
+            let dsts = strategy.distances(game.occupied()).map(|(_, d)| d).collect_vec();
+            return skip_distance(&dsts, probabilities, &mut Vec::new());
+        }
+
+        let mut next = *game;
+        next.perform_placement(placement);
+        let dsts = strategy.distances(next.occupied()).map(|(_, d)| d).collect_vec();
+        1.0 + avg_dst(&dsts, probabilities)
+    }
+
+    #[test]
+    fn test_symmetry_reduction() {
+        let dims = Dimensions::new(3, 4);
+        for orientations in [Orientations::Fixed, Orientations::RotationsAndReflections] {
+            let mut full = Deterministic::unreduced(dims, FigureSet::standard(), orientations);
+            full.run();
+            let mut reduced = Deterministic::with_orientations(dims, FigureSet::standard(), orientations);
+            reduced.run();
+            assert!(reduced.boards() < full.boards());
+
+            // mirror boards sum the distances of their figures in a different
+            // order, so values may differ in the last bit.
+            let mut game = Jigsaw::new(dims);
+            game.orientations = orientations;
+            for board in 0..1 << dims.cells() {
+                game.board = board;
+                for ((_, a), (_, b)) in reduced.distances(board).zip(full.distances(board)) {
+                    assert!(a == b || (a - b).abs() <= 1e-5 * b);
+                }
+                // ties between moves may be broken the other way round.
+                for figure in 0..FIGURES.len() as u8 {
+                    game.figure = figure;
+                    let a = value(&full, &game, reduced.placement(&game));
+                    let b = value(&full, &game, full.placement(&game));
+                    assert!(a == b || (a - b).abs() <= 1e-5 * b);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_memory() {
+        let dims = Dimensions::new(3, 4);
+        let set = FigureSet::standard();
+        let strategy = Deterministic::with_figures(dims, set.clone());
+        assert_eq!(Deterministic::estimate_memory(dims, &set, Orientations::Fixed), strategy.memory());
+
+        // the half turn keeps about half of the boards, each one storing a
+        // distance and an action per figure.
+        let full = Deterministic::unreduced(dims, set, Orientations::Fixed);
+        assert_eq!(full.memory(), (1 << 12) * 6 * 5);
+        assert!(strategy.memory() < full.memory() * 6 / 10);
+    }
+
+    #[test]
+    fn test_obstacles() {
+        let dims = Dimensions::new(2, 3);
+        let mut strategy = Deterministic::with_dimensions(dims, FigureDistribution::default());
+        strategy.run();
+
+        // obstacles are solved as cells filled before the game started.
+        let blocked: Jigsaw = "X../X.. 0 0".parse().unwrap();
+        let filled: Jigsaw = "#../#.. 0 0".parse().unwrap();
+        assert_eq!(blocked.empty_cells(), 4);
+        assert_eq!(strategy.solve(&blocked), strategy.solve(&filled));
+        assert_eq!(strategy.expected_pieces(&blocked), strategy.expected_pieces(&filled));
+        assert!(strategy.expected_pieces(&blocked) < strategy.expected_pieces(&Jigsaw::new(dims)));
+
+        let finished: Jigsaw = "XXX/### 0 5".parse().unwrap();
+        assert_eq!(strategy.expected_pieces(&finished), 0.0);
+    }
+
+    #[test]
+    fn test_orientations() {
+        // the vertical bar only fits a 1x3 board once turned.
+        let dims = Dimensions::new(1, 3);
+        let set: FigureSet = "#/#/#".parse().unwrap();
+        let mut fixed = Deterministic::with_figures(dims, set.clone());
+        fixed.run();
+        let mut rotating = Deterministic::with_orientations(dims, set.clone(), Orientations::Rotations);
+        rotating.run();
+
+        let mut game = Jigsaw::new(dims);
+        game.figures = set.figures();
+        assert_eq!(fixed.solve(&game), Action::Skip);
+        assert_eq!(fixed.distances(0).next().unwrap().1, f32::INFINITY);
+
+        game.orientations = Orientations::Rotations;
+        let placement = rotating.placement(&game);
+        assert_eq!(placement, Placement { orientation: 1, action: (0, 0).into() });
+        assert_eq!(rotating.distances(0).next(), Some(((0, 0).into(), 1.0)));
+
+        game.perform_placement(placement);
+        assert!(game.has_finished());
+
+        let mut bytes = Vec::new();
+        rotating.save(&mut bytes).unwrap();
+        let loaded = Deterministic::load(bytes.as_slice()).unwrap();
+        assert_eq!(loaded.orientations(), Orientations::Rotations);
+        assert_eq!(loaded.placement(&Jigsaw { board: 0, ..game }), placement);
+    }
+
+    #[test]
+    #[should_panic(expected = "use `Solver::placement`")]
+    fn test_solve_rejects_orientations() {
+        let dims = Dimensions::new(1, 3);
+        let set: FigureSet = "#/#/#".parse().unwrap();
+        let mut strategy = Deterministic::with_orientations(dims, set.clone(), Orientations::Rotations);
+        strategy.run();
+
+        // the recommended placement turns the bar, so it cannot be played as
+        // an action on the figure as drawn.
+        let mut game = Jigsaw::new(dims);
+        game.figures = set.figures();
+        game.orientations = Orientations::Rotations;
+        game.perform_action(strategy.solve(&game));
+    }
+
+    #[test]
+    fn test_parallel() {
+        let dims = Dimensions::new(3, 4);
+        let set = FigureSet::standard();
+        for orientations in [Orientations::Fixed, Orientations::RotationsAndReflections] {
+            let mut serial = Deterministic::with_orientations(dims, set.clone(), orientations);
+            serial.run();
+            let mut parallel = Deterministic::with_orientations(dims, set.clone(), orientations);
+            parallel.run_parallel();
+
+            let (mut expected, mut bytes) = (Vec::new(), Vec::new());
+            serial.save(&mut expected).unwrap();
+            parallel.save(&mut bytes).unwrap();
+            assert_eq!(bytes, expected);
+        }
+    }
+
+    #[test]
+    fn test_progress() {
+        let dims = Dimensions::new(2, 3);
+        for parallel in [false, true] {
+            let mut strategy = Deterministic::with_dimensions(dims, FigureDistribution::default());
+            let mut layers = Vec::new();
+            let progress = |layer| {
+                layers.push(layer);
+                ControlFlow::Continue(())
+            };
+            let flow = if parallel {
+                strategy.run_parallel_with_progress(progress)
+            } else {
+                strategy.run_with_progress(progress)
+            };
+            assert_eq!(flow, ControlFlow::Continue(()));
+            assert_eq!(layers, (1..=dims.cells()).collect_vec());
+
+            // cancelling after the third layer stops the build right there.
+            let mut strategy = Deterministic::with_dimensions(dims, FigureDistribution::default());
+            let mut layers = Vec::new();
+            let progress = |layer| {
+                layers.push(layer);
+                if layer == 3 { ControlFlow::Break(()) } else { ControlFlow::Continue(()) }
+            };
+            let flow = if parallel {
+                strategy.run_parallel_with_progress(progress)
+            } else {
+                strategy.run_with_progress(progress)
+            };
+            assert_eq!(flow, ControlFlow::Break(()));
+            assert_eq!(layers, vec![1, 2, 3]);
+        }
+    }
+
+    #[test]
+    fn test_action_values() {
+        let dims = Dimensions::new(2, 3);
+        let mut strategy = Deterministic::with_dimensions(dims, FigureDistribution::default());
+        strategy.run();
+
+        let game: Jigsaw = "#../... 0 0".parse().unwrap();
+        let values = strategy.action_values(&game);
+        assert_eq!(values.len(), game.legal_placements().len());
+        assert!(values.windows(2).all(|w| w[0].1 <= w[1].1));
+
+        // the best value is the one the table recommends and expects, up to
+        // the order the distances are summed in.
+        assert!((values[0].1 - strategy.expected_pieces(&game)).abs() < 1e-5);
+        assert!((value(&strategy, &game, strategy.placement(&game)) - values[0].1).abs() < 1e-5);
+        for &(placement, v) in &values {
+            assert!((value(&strategy, &game, placement) - v).abs() < 1e-5);
+        }
+
+        // filling the last hole ends the game with this piece.
+        let game: Jigsaw = "###/##. 0 3".parse().unwrap();
+        assert_eq!(strategy.action_values(&game)[0], (Placement { orientation: 0, action: (2, 1).into() }, 1.0));
+        assert!(strategy.action_values(&"###/### 0 4".parse().unwrap()).is_empty());
+    }
+}
@@ -0,0 +1,156 @@
+use std::collections::HashMap;
+
+use crate::jigsaw::{FigureDistribution, Jigsaw};
+use crate::solver::Solver;
+
+/// Exact probability mass function of the final `Jigsaw::round` when playing
+/// `game` until the end following `solver`, with the next figures drawn from
+/// `figures`, where `pmf[r]` is the probability of finishing at round `r`.
+///
+/// Every board on the frontier shares the same round, so the mass is pushed
+/// forward one round at a time. Rounds after `max_round` are not expanded,
+/// and their mass is simply missing from the returned vector.
+pub fn round_distribution<S: Solver + ?Sized>(
+    solver: &S,
+    game: &Jigsaw,
+    figures: &FigureDistribution,
+    max_round: u8,
+) -> Vec<f64> {
+    let mut pmf = vec![0.0; max_round as usize + 1];
+
+    if game.has_finished() {
+        if game.round <= max_round {
+            pmf[game.round as usize] = 1.0;
+        }
+        return pmf;
+    }
+
+    // the current figure is already known, the following ones are not.
+    let mut first = *game;
+    first.perform_placement(solver.placement(game));
+
+    let mut frontier = HashMap::from([(first.board, 1.0)]);
+
+    // `max_round` may be the last `u8`, so rounds are never counted past it.
+    for round in first.round..=max_round {
+        if frontier.is_empty() {
+            break;
+        }
+
+        let mut next = HashMap::with_capacity(frontier.len());
+
+        for (board, mass) in frontier {
+            if board | game.obstacles == game.dims.terminal_state::<u32>() {
+                pmf[round as usize] += mass;
+                continue;
+            }
+            if round == max_round {
+                continue;
+            }
+
+            for figure in 0..figures.probabilities().len() as u8 {
+                let p = figures.probability(figure) as f64;
+                if p == 0.0 {
+                    continue;
+                }
+
+                let mut game = Jigsaw {
+                    board,
+                    obstacles: game.obstacles,
+                    figure,
+                    round,
+                    dims: game.dims,
+                    figures: game.figures,
+                    orientations: game.orientations,
+                };
+                game.perform_placement(solver.placement(&game));
+
+                *next.entry(game.board).or_insert(0.0) += mass * p;
+            }
+        }
+
+        frontier = next;
+    }
+
+    pmf
+}
+
+#[cfg(test)]
+mod distribution_test {
+    use super::*;
+    use crate::jigsaw::{Action, TERMINAL_STATE, TOTAL_CELLS};
+
+    struct FirstLegal;
+
+    impl Solver for FirstLegal {
+        fn solve(&self, game: &Jigsaw) -> Action {
+            game.legal_actions()[0]
+        }
+    }
+
+    #[test]
+    fn test_finished_game() {
+        let game = Jigsaw {
+            board: TERMINAL_STATE,
+            figure: 0,
+            round: 7,
+            ..Default::default()
+        };
+
+        let pmf = round_distribution(&FirstLegal, &game, &FigureDistribution::default(), 10);
+        assert_eq!(pmf[7], 1.0);
+        assert_eq!(pmf.iter().sum::<f64>(), 1.0);
+    }
+
+    #[test]
+    fn test_single_hole() {
+        let game = Jigsaw {
+            board: TERMINAL_STATE & !(1 << (TOTAL_CELLS - 1)),
+            figure: 1,
+            round: 0,
+            ..Default::default()
+        };
+        assert_eq!(FirstLegal.solve(&game), Action::Skip);
+
+        // the hole only fits the single cell figure, drawn with p = 1/6.
+        let pmf = round_distribution(&FirstLegal, &game, &FigureDistribution::default(), 40);
+        assert_eq!(pmf[0], 0.0);
+        assert_eq!(pmf[1], 0.0);
+        for (k, &p) in pmf.iter().enumerate().skip(2) {
+            let expected = (5.0f64 / 6.0).powi(k as i32 - 2) / 6.0;
+            assert!((p - expected).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_last_round() {
+        // the hole never fits a square, so the game never ends.
+        let game = Jigsaw {
+            board: TERMINAL_STATE & !(1 << (TOTAL_CELLS - 1)),
+            figure: 4,
+            round: 0,
+            ..Default::default()
+        };
+
+        let figures = FigureDistribution::new(&[0.0, 0.0, 0.0, 0.0, 1.0, 0.0]);
+        let pmf = round_distribution(&FirstLegal, &game, &figures, u8::MAX);
+        assert_eq!(pmf.len(), 256);
+        assert_eq!(pmf.iter().sum::<f64>(), 0.0);
+    }
+
+    #[test]
+    fn test_weighted_single_hole() {
+        let game = Jigsaw {
+            board: TERMINAL_STATE & !(1 << (TOTAL_CELLS - 1)),
+            figure: 1,
+            round: 0,
+            ..Default::default()
+        };
+
+        let figures = FigureDistribution::new(&[1.0, 1.0, 0.0, 0.0, 0.0, 0.0]);
+        let pmf = round_distribution(&FirstLegal, &game, &figures, 40);
+        for (k, &p) in pmf.iter().enumerate().skip(2) {
+            assert!((p - 0.5f64.powi(k as i32 - 1)).abs() < 1e-6);
+        }
+    }
+}
@@ -0,0 +1,310 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Mutex;
+
+use crate::jigsaw::{Dimensions, Figure, FigureDistribution, ParseFigureError, FIGURES};
+
+/// Most figures a set can have, figures being indexed by `u8`.
+pub const MAX_FIGURES: usize = u8::MAX as usize;
+
+/// Figures that can be drawn in a game together with their drop
+/// probabilities, `FIGURES` with a uniform distribution by default.
+///
+/// Sets can be read from a file with one figure per line, written in the
+/// grid notation of `Figure` and optionally followed by its drop weight
+/// (1 when missing). Blank lines and lines starting with `//` are ignored:
+///
+/// ```text
+/// // shape   weight
+/// #          2
+/// #/#/#
+/// ##./.##    0.5
+/// ```
+#[derive(Clone, Debug)]
+pub struct FigureSet {
+    figures: &'static [Figure],
+    distribution: FigureDistribution,
+    // line of each figure in the text it was read from, empty for sets built
+    // in code, see `validate`.
+    lines: Vec<usize>,
+}
+
+// sets read from different files are the same set.
+impl PartialEq for FigureSet {
+    fn eq(&self, other: &Self) -> bool {
+        self.figures == other.figures && self.distribution == other.distribution
+    }
+}
+
+impl FigureSet {
+    pub fn standard() -> Self {
+        Self {
+            figures: &FIGURES,
+            distribution: FigureDistribution::default(),
+            lines: Vec::new(),
+        }
+    }
+
+    /// Builds a set from `figures` drawn with `distribution`. Figures are
+    /// shared by every `Jigsaw` of the set so they are leaked, once for each
+    /// distinct list of figures: building the same figures again, say when
+    /// reloading a file, reuses them.
+    pub fn new(figures: Vec<Figure>, distribution: FigureDistribution) -> Self {
+        assert!(!figures.is_empty() && figures.len() <= MAX_FIGURES);
+        assert_eq!(figures.len(), distribution.probabilities().len());
+
+        Self {
+            figures: intern(figures),
+            distribution,
+            lines: Vec::new(),
+        }
+    }
+
+    /// Same figures drawn with a different `distribution`.
+    pub fn with_distribution(&self, distribution: FigureDistribution) -> Self {
+        assert_eq!(self.figures.len(), distribution.probabilities().len());
+
+        Self {
+            figures: self.figures,
+            distribution,
+            lines: self.lines.clone(),
+        }
+    }
+
+    pub fn figures(&self) -> &'static [Figure] {
+        self.figures
+    }
+
+    pub fn distribution(&self) -> &FigureDistribution {
+        &self.distribution
+    }
+
+    /// Checks that every figure can be placed on a board of `dims`. Sets read
+    /// from text report the line of the first figure that does not fit.
+    pub fn validate(&self, dims: Dimensions) -> Result<(), FigureSetError> {
+        match self.figures.iter().position(|f| f.max_offset(dims).is_none()) {
+            Some(figure) => Err(FigureSetError::DoesNotFit {
+                figure,
+                line: self.lines.get(figure).copied(),
+                dims,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    pub fn load_from_file<P: AsRef<Path>>(path: P) -> Result<Self, FigureSetError> {
+        fs::read_to_string(path)?.parse()
+    }
+}
+
+// every list of figures leaked by `FigureSet::new` so far.
+static LEAKED: Mutex<Vec<&'static [Figure]>> = Mutex::new(Vec::new());
+
+fn intern(figures: Vec<Figure>) -> &'static [Figure] {
+    if figures == FIGURES {
+        return &FIGURES;
+    }
+
+    let mut leaked = LEAKED.lock().unwrap();
+    if let Some(&shared) = leaked.iter().find(|&&shared| shared == figures.as_slice()) {
+        return shared;
+    }
+
+    let shared = Box::leak(figures.into_boxed_slice());
+    leaked.push(shared);
+    shared
+}
+
+impl Default for FigureSet {
+    fn default() -> Self {
+        Self::standard()
+    }
+}
+
+/// Error returned when reading a `FigureSet`. Lines and figures are 1-based
+/// and 0-based respectively, figures being referred to by their index.
+#[derive(Debug)]
+pub enum FigureSetError {
+    Io(io::Error),
+    Shape { line: usize, error: ParseFigureError },
+    Weight { line: usize, found: String },
+    TrailingInput { line: usize, found: String },
+    Empty,
+    TooMany(usize),
+    Weights,
+    DoesNotFit { figure: usize, line: Option<usize>, dims: Dimensions },
+}
+
+impl fmt::Display for FigureSetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "{}", err),
+            Self::Shape { line, error } => write!(f, "line {}: {}", line, error),
+            Self::Weight { line, found } => {
+                write!(f, "line {}: invalid weight `{}`", line, found)
+            }
+            Self::TrailingInput { line, found } => {
+                write!(f, "line {}: unexpected `{}` after weight", line, found)
+            }
+            Self::Empty => write!(f, "figure set has no figures"),
+            Self::TooMany(found) => {
+                write!(f, "figure set has {} figures, at most {} are supported", found, MAX_FIGURES)
+            }
+            Self::Weights => write!(f, "weights must be non-negative and not all zero"),
+            Self::DoesNotFit {
+                line: Some(line),
+                dims,
+                ..
+            } => write!(
+                f,
+                "line {}: figure does not fit in a {}x{} board",
+                line, dims.rows, dims.columns
+            ),
+            Self::DoesNotFit { figure, dims, .. } => write!(
+                f,
+                "figure {} does not fit in a {}x{} board",
+                figure, dims.rows, dims.columns
+            ),
+        }
+    }
+}
+
+impl std::error::Error for FigureSetError {}
+
+impl From<io::Error> for FigureSetError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl FromStr for FigureSet {
+    type Err = FigureSetError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut figures = Vec::new();
+        let mut weights = Vec::new();
+        let mut lines = Vec::new();
+
+        for (i, line) in s.lines().enumerate() {
+            let line_number = i + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with("//") {
+                continue;
+            }
+
+            let mut tokens = line.split_whitespace();
+            let shape = tokens.next().unwrap();
+            let figure = shape.parse::<Figure>().map_err(|error| FigureSetError::Shape {
+                line: line_number,
+                error,
+            })?;
+
+            let weight = match tokens.next() {
+                Some(token) => token
+                    .parse::<f32>()
+                    .ok()
+                    .filter(|w| w.is_finite() && *w >= 0.0)
+                    .ok_or_else(|| FigureSetError::Weight {
+                        line: line_number,
+                        found: token.to_string(),
+                    })?,
+                None => 1.0,
+            };
+
+            if let Some(token) = tokens.next() {
+                return Err(FigureSetError::TrailingInput {
+                    line: line_number,
+                    found: token.to_string(),
+                });
+            }
+
+            figures.push(figure);
+            weights.push(weight);
+            lines.push(line_number);
+        }
+
+        if figures.is_empty() {
+            return Err(FigureSetError::Empty);
+        }
+        if figures.len() > MAX_FIGURES {
+            return Err(FigureSetError::TooMany(figures.len()));
+        }
+        if !FigureDistribution::is_valid(&weights) {
+            return Err(FigureSetError::Weights);
+        }
+
+        let set = Self::new(figures, FigureDistribution::new(&weights));
+        Ok(Self { lines, ..set })
+    }
+}
+
+#[cfg(test)]
+mod figure_set_test {
+    use super::*;
+    use crate::jigsaw::STANDARD;
+
+    #[test]
+    fn test_parse() {
+        let set: FigureSet = "
+            // the standard figures, squares twice as likely
+            #
+            #/#/#
+            #./##
+            ##/.#
+            ##/## 2
+            ##./.##
+        "
+        .parse()
+        .unwrap();
+
+        assert_eq!(set.figures(), &FIGURES);
+        assert_eq!(set.distribution().probability(4), 2.0 / 7.0);
+        assert_eq!(set.distribution().probability(0), 1.0 / 7.0);
+        assert!(set.validate(STANDARD).is_ok());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = |s: &str| s.parse::<FigureSet>().err().unwrap();
+
+        assert!(matches!(error(""), FigureSetError::Empty));
+        assert!(matches!(error("#\n#x"), FigureSetError::Shape { line: 2, .. }));
+        assert!(matches!(error("# -1"), FigureSetError::Weight { line: 1, .. }));
+        assert!(matches!(error("# 1 2"), FigureSetError::TrailingInput { line: 1, .. }));
+        assert!(matches!(error("# 0\n## 0"), FigureSetError::Weights));
+    }
+
+    #[test]
+    fn test_figures_leaked_once() {
+        let a: FigureSet = "#\n##/##".parse().unwrap();
+        let b: FigureSet = "#\n##/## 3".parse().unwrap();
+        assert!(std::ptr::eq(a.figures(), b.figures()));
+
+        let other: FigureSet = "#\n###".parse().unwrap();
+        assert!(!std::ptr::eq(a.figures(), other.figures()));
+
+        let standard = FigureSet::new(FIGURES.to_vec(), FigureDistribution::default());
+        assert!(std::ptr::eq(standard.figures(), FigureSet::standard().figures()));
+    }
+
+    #[test]
+    fn test_validate() {
+        let set: FigureSet = "#\n#####".parse().unwrap();
+        assert!(set.validate(Dimensions::new(4, 5)).is_ok());
+
+        let error = set.validate(Dimensions::new(6, 4)).err().unwrap();
+        assert!(matches!(error, FigureSetError::DoesNotFit { figure: 1, line: Some(2), .. }));
+
+        // comments and blank lines count, the error names the line as written.
+        let set: FigureSet = "// bars\n\n#\n#####".parse().unwrap();
+        let error = set.validate(Dimensions::new(6, 4)).err().unwrap();
+        assert_eq!(error.to_string(), "line 4: figure does not fit in a 6x4 board");
+
+        let set = FigureSet::new(set.figures().to_vec(), set.distribution().clone());
+        let error = set.validate(Dimensions::new(6, 4)).err().unwrap();
+        assert_eq!(error.to_string(), "figure 1 does not fit in a 6x4 board");
+    }
+}
@@ -0,0 +1,207 @@
+use crate::jigsaw::{Action, Jigsaw, Orientations, Placement};
+use crate::solver::Solver;
+use rand::prelude::*;
+
+// placement of `game` with the highest score, the first one on ties. Every
+// heuristic only skips when the figure does not fit anywhere.
+fn best_placement<F>(game: &Jigsaw, mut score: F) -> Placement
+where
+    F: FnMut(&Jigsaw, Placement) -> i32,
+{
+    let mut placements = game.legal_placements();
+    let skip = placements.pop().unwrap();
+
+    let mut best = None;
+    for placement in placements {
+        let value = score(game, placement);
+        if best.is_none_or(|(_, v)| value > v) {
+            best = Some((placement, value));
+        }
+    }
+
+    best.map_or(skip, |(placement, _)| placement)
+}
+
+// heuristics answer `solve` as if figures could not be turned.
+fn fixed(game: &Jigsaw) -> Jigsaw {
+    Jigsaw {
+        orientations: Orientations::Fixed,
+        ..*game
+    }
+}
+
+/// Places the current figure at a random legal position, skipping only when
+/// it does not fit. The position only depends on the game and the seed, so
+/// the policy is well defined for `round_distribution`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RandomLegal {
+    seed: u64,
+}
+
+impl RandomLegal {
+    pub fn new(seed: u64) -> Self {
+        Self { seed }
+    }
+}
+
+impl Solver for RandomLegal {
+    fn solve(&self, game: &Jigsaw) -> Action {
+        self.placement(&fixed(game)).action
+    }
+
+    fn placement(&self, game: &Jigsaw) -> Placement {
+        let mut placements = game.legal_placements();
+        let skip = placements.pop().unwrap();
+
+        let seed = self.seed ^ (game.occupied() as u64) << 16 ^ (game.round as u64) << 8 ^ game.figure as u64;
+        let mut rng = StdRng::seed_from_u64(seed);
+        placements.choose(&mut rng).copied().unwrap_or(skip)
+    }
+}
+
+/// Places the current figure at the first legal position in action order,
+/// skipping only when it does not fit.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FirstLegal;
+
+impl Solver for FirstLegal {
+    fn solve(&self, game: &Jigsaw) -> Action {
+        self.placement(&fixed(game)).action
+    }
+
+    fn placement(&self, game: &Jigsaw) -> Placement {
+        best_placement(game, |_, _| 0)
+    }
+}
+
+/// Fills as many cells as it can this round, which means never skipping a
+/// figure that fits, and among those placements keeps the board open: the
+/// one leaving the most legal placements for the figures of the set.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Greedy;
+
+impl Solver for Greedy {
+    fn solve(&self, game: &Jigsaw) -> Action {
+        self.placement(&fixed(game)).action
+    }
+
+    fn placement(&self, game: &Jigsaw) -> Placement {
+        best_placement(game, |game, placement| {
+            let mut next = *game;
+            next.perform_placement(placement);
+
+            (0..game.figures.len() as u8)
+                .map(|figure| {
+                    next.figure = figure;
+                    next.legal_placements().len() as i32 - 1
+                })
+                .sum()
+        })
+    }
+}
+
+/// Places the current figure against the walls and the occupied cells as
+/// much as it can, counting the sides of its cells that touch either, so the
+/// board fills from the corners inwards and leaves few isolated holes.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct EdgeHugging;
+
+impl Solver for EdgeHugging {
+    fn solve(&self, game: &Jigsaw) -> Action {
+        self.placement(&fixed(game)).action
+    }
+
+    fn placement(&self, game: &Jigsaw) -> Placement {
+        best_placement(game, |game, placement| {
+            let mut next = *game;
+            next.perform_placement(placement);
+            let placed = next.board & !game.board;
+            let dims = game.dims;
+
+            let blocked = |x: i16, y: i16| {
+                if x < 0 || y < 0 || x >= dims.columns as i16 || y >= dims.rows as i16 {
+                    return true;
+                }
+                game.occupied() & dims.mask::<u32>((x as u8, y as u8)) != 0
+            };
+
+            (0..dims.columns)
+                .flat_map(|x| (0..dims.rows).map(move |y| (x, y)))
+                .filter(|&offsets| placed & dims.mask::<u32>(offsets) != 0)
+                .map(|(x, y)| {
+                    let (x, y) = (x as i16, y as i16);
+                    [(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)]
+                        .into_iter()
+                        .filter(|&(x, y)| blocked(x, y))
+                        .count() as i32
+                })
+                .sum()
+        })
+    }
+}
+
+#[cfg(test)]
+mod heuristic_test {
+    use super::*;
+
+    #[test]
+    fn test_never_skip() {
+        let game: Jigsaw = "######/######/######/##.... 0 5".parse().unwrap();
+        let solvers: [&dyn Solver; 4] = [&RandomLegal::new(7), &FirstLegal, &Greedy, &EdgeHugging];
+        for solver in solvers {
+            let action = solver.solve(&game);
+            assert!(!action.is_skip());
+            assert!(game.is_legal(action));
+        }
+
+        // the square does not fit any of the holes left.
+        let game: Jigsaw = "#.####/######/####.#/###### 4 5".parse().unwrap();
+        for solver in solvers {
+            assert_eq!(solver.solve(&game), Action::Skip);
+        }
+    }
+
+    #[test]
+    fn test_heuristics() {
+        let game: Jigsaw = "#...../....../....../...... 0 0".parse().unwrap();
+        assert_eq!(FirstLegal.solve(&game), Action::Place { x: 0, y: 1 });
+        // the cell below the filled one touches it and a wall, the first
+        // placement doing so.
+        assert_eq!(EdgeHugging.solve(&game).offsets(), Some((0, 1)));
+
+        let random = RandomLegal::new(3);
+        assert_eq!(random.solve(&game), random.solve(&game));
+
+        // filling the top right corner leaves room for the square and both
+        // L shaped figures.
+        let game: Jigsaw = ".../..# 0 0".parse().unwrap();
+        assert_eq!(FirstLegal.solve(&game), Action::Place { x: 0, y: 0 });
+        assert_eq!(Greedy.solve(&game).offsets(), Some((2, 0)));
+    }
+
+    #[test]
+    fn test_turned_placements() {
+        // the only hole left is a row of three cells, which the vertical bar
+        // only fills once turned.
+        let mut game: Jigsaw = "######/######/###.../###### 1 5".parse().unwrap();
+        let solvers: [&dyn Solver; 4] = [&RandomLegal::new(7), &FirstLegal, &Greedy, &EdgeHugging];
+        for orientations in [Orientations::Rotations, Orientations::RotationsAndReflections] {
+            game.orientations = orientations;
+            for solver in solvers {
+                let placement = solver.placement(&game);
+                assert!(!placement.action.is_skip());
+                assert_ne!(placement.orientation, 0);
+                assert!(game.is_legal_placement(placement));
+            }
+        }
+    }
+
+    #[test]
+    fn test_edge_hugging_corners() {
+        // the first square that fits leans on a single filled cell, while the
+        // corners below and to the right touch two walls.
+        let game: Jigsaw = "#.../..../..../.... 4 0".parse().unwrap();
+        assert_eq!(FirstLegal.solve(&game), Action::Place { x: 0, y: 1 });
+        assert_eq!(EdgeHugging.solve(&game), Action::Place { x: 0, y: 2 });
+    }
+}
@@ -0,0 +1,168 @@
+use crate::jigsaw::{Action, Jigsaw};
+use crate::practice::Grade;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Change {
+    /// `grade` is the practice grade of the move, taken back with it.
+    Move {
+        action: Action,
+        recommended: Action,
+        grade: Option<Grade>,
+    },
+    /// Cells, figure or round changed by hand.
+    Edit,
+    Reset,
+}
+
+/// A single step of the game, with the states around it so it can be undone
+/// and redone. `after` already holds the next random figure.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Turn {
+    pub before: Jigsaw,
+    pub after: Jigsaw,
+    pub change: Change,
+}
+
+impl Turn {
+    /// Whether the action taken was the recommended one, `None` for edits
+    /// and resets.
+    pub fn followed(&self) -> Option<bool> {
+        match self.change {
+            Change::Move {
+                action,
+                recommended,
+                ..
+            } => Some(action == recommended),
+            Change::Edit | Change::Reset => None,
+        }
+    }
+}
+
+/// Undo/redo stack of turns. Turns after `cursor` have been undone and are
+/// dropped as soon as a new turn is pushed.
+#[derive(Default)]
+pub struct History {
+    turns: Vec<Turn>,
+    cursor: usize,
+}
+
+impl History {
+    pub fn push(&mut self, turn: Turn) {
+        self.turns.truncate(self.cursor);
+        self.turns.push(turn);
+        self.cursor += 1;
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.cursor > 0
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.cursor < self.turns.len()
+    }
+
+    /// Steps back one turn, returning it so its `before` state is restored.
+    pub fn undo(&mut self) -> Option<&Turn> {
+        if !self.can_undo() {
+            return None;
+        }
+
+        self.cursor -= 1;
+        Some(&self.turns[self.cursor])
+    }
+
+    /// Steps forward one turn, returning it so its `after` state is restored.
+    pub fn redo(&mut self) -> Option<&Turn> {
+        if !self.can_redo() {
+            return None;
+        }
+
+        self.cursor += 1;
+        Some(&self.turns[self.cursor - 1])
+    }
+
+    /// Turns currently applied, oldest first.
+    pub fn turns(&self) -> &[Turn] {
+        &self.turns[..self.cursor]
+    }
+}
+
+#[cfg(test)]
+mod history_test {
+    use super::*;
+
+    fn turn(round: u8) -> Turn {
+        let before = Jigsaw {
+            round,
+            ..Default::default()
+        };
+        let after = Jigsaw {
+            round: round + 1,
+            ..Default::default()
+        };
+
+        Turn {
+            before,
+            after,
+            change: Change::Move {
+                action: Action::Place { x: round, y: 0 },
+                recommended: Action::Place { x: 0, y: 0 },
+                grade: None,
+            },
+        }
+    }
+
+    #[test]
+    fn test_undo_redo() {
+        let mut history = History::default();
+        assert!(history.undo().is_none());
+
+        history.push(turn(0));
+        history.push(turn(1));
+        assert_eq!(history.turns().len(), 2);
+
+        assert_eq!(history.undo().unwrap().before.round, 1);
+        assert_eq!(history.undo().unwrap().before.round, 0);
+        assert!(history.undo().is_none());
+
+        assert_eq!(history.redo().unwrap().after.round, 1);
+        assert_eq!(history.turns(), &[turn(0)]);
+        assert!(history.can_redo());
+    }
+
+    #[test]
+    fn test_push_drops_redo() {
+        let mut history = History::default();
+        history.push(turn(0));
+        history.push(turn(1));
+        history.undo();
+
+        history.push(turn(5));
+        assert!(!history.can_redo());
+        assert_eq!(history.turns(), &[turn(0), turn(5)]);
+
+        assert_eq!(history.turns()[0].followed(), Some(true));
+        assert_eq!(history.turns()[1].followed(), Some(false));
+    }
+
+    #[test]
+    fn test_edits() {
+        let mut history = History::default();
+        history.push(turn(0));
+
+        // an edit after the move is undone before it.
+        let mut edited = turn(0).after;
+        edited.figure = 3;
+        history.push(Turn {
+            before: turn(0).after,
+            after: edited,
+            change: Change::Edit,
+        });
+        assert_eq!(history.turns()[1].followed(), None);
+
+        assert_eq!(history.undo().unwrap().before, turn(0).after);
+        assert_eq!(history.undo().unwrap().before, turn(0).before);
+        assert_eq!(history.redo().unwrap().after, turn(0).after);
+        assert_eq!(history.redo().unwrap().after, edited);
+    }
+}
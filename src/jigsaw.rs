@@ -1,6 +1,12 @@
 use std::fmt;
+use std::str::FromStr;
+use rand::distributions::WeightedIndex;
 use rand::prelude::*;
 
+use crate::board::Board;
+
+// size of the standard fishing jigsaw board, other sizes are described by
+// `Dimensions` instead.
 pub const N: u8 = 4;
 pub const M: u8 = 6;
 pub const SKIP_ACTION: u8 = N * M;
@@ -10,178 +16,708 @@ pub const TOTAL_ACTIONS: u8 = TOTAL_CELLS + 1;
 pub const TERMINAL_STATE: u32 = (1 << (N * M)) - 1;
 pub const INIT_STATE: u32 = 0;
 
+/// Size of a board with `rows` (`N`) and `columns` (`M`). Cells are stored
+/// column by column from the most significant bit of the board, see `Board`,
+/// so a board can have at most `MAX_CELLS` cells.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "serde_impl::RawDimensions"))]
+pub struct Dimensions {
+    pub rows: u8,
+    pub columns: u8,
+}
+
+pub const STANDARD: Dimensions = Dimensions::new(N, M);
+
+/// Cells of the widest `Board`, `u128`.
+pub const MAX_CELLS: u32 = u128::BITS;
+
+impl Dimensions {
+    pub const fn new(rows: u8, columns: u8) -> Self {
+        assert!(Self::is_valid(rows, columns));
+        Self { rows, columns }
+    }
+
+    pub const fn is_valid(rows: u8, columns: u8) -> bool {
+        rows > 0 && columns > 0 && rows as u32 * columns as u32 <= MAX_CELLS
+    }
+
+    /// Whether boards of this size can be stored in a `B`.
+    pub fn fits<B: Board>(&self) -> bool {
+        self.cells() as u32 <= B::BITS
+    }
+
+    pub const fn cells(&self) -> u8 {
+        self.rows * self.columns
+    }
+
+    /// Actions are the cells of the board followed by the skip action.
+    pub const fn skip_action(&self) -> u8 {
+        self.cells()
+    }
+
+    pub fn terminal_state<B: Board>(&self) -> B {
+        B::full(self.cells())
+    }
+
+    pub const fn action_to_offsets(&self, action: u8) -> (u8, u8) {
+        (action / self.rows, action % self.rows)
+    }
+
+    pub const fn offset_to_action(&self, offsets: (u8, u8)) -> u8 {
+        offsets.0 * self.rows + offsets.1
+    }
+
+    pub fn mask<B: Board>(&self, offsets: (u8, u8)) -> B {
+        B::bit(self.cells() - 1 - self.offset_to_action(offsets))
+    }
+}
+
+impl Default for Dimensions {
+    fn default() -> Self {
+        STANDARD
+    }
+}
+
+/// Width of the grid `Figure::shape` is stored in.
+pub const FIGURE_SPAN: u8 = 8;
+
+/// Cells of a figure relative to the top left corner of its bounding box,
+/// independent of the board it is placed on.
+///
+/// With the `serde` feature figures are written in their grid notation, see
+/// `Display`, so the fields cannot contradict each other.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "String", into = "String"))]
 pub struct Figure {
-    pub value: u32,
+    /// cell `(x, y)` is bit `y * FIGURE_SPAN + x`.
+    pub shape: u64,
     pub size: u8,
-    pub max_offset: (u8, u8),
+    pub width: u8,
+    pub height: u8,
+}
+
+impl Figure {
+    /// Builds a figure from its `(x, y)` cells. The figure is moved so its
+    /// bounding box starts at `(0, 0)`.
+    pub const fn from_cells(cells: &[(u8, u8)]) -> Self {
+        let mut shape = 0u64;
+
+        let mut i = 0;
+        while i < cells.len() {
+            let (x, y) = cells[i];
+            assert!(x < FIGURE_SPAN && y < FIGURE_SPAN);
+
+            shape |= 1 << (y * FIGURE_SPAN + x);
+            i += 1;
+        }
+
+        Self::from_shape(shape)
+    }
+
+    /// Builds a figure from a grid drawn row by row from top to bottom, with
+    /// `#` for the cells of the figure and `.` for empty cells, e.g.
+    /// `["##.", ".##"]`. Meant for figure tables, use `FromStr` to get an
+    /// error instead of a panic on malformed grids.
+    pub const fn from_rows(rows: &[&str]) -> Self {
+        assert!(rows.len() <= FIGURE_SPAN as usize);
+        let mut shape = 0u64;
+
+        let mut y = 0;
+        while y < rows.len() {
+            let row = rows[y].as_bytes();
+            assert!(row.len() <= FIGURE_SPAN as usize);
+
+            let mut x = 0;
+            while x < row.len() {
+                match row[x] {
+                    b'#' => shape |= 1 << (y * FIGURE_SPAN as usize + x),
+                    b'.' => {}
+                    _ => panic!("figure cells must be `#` or `.`"),
+                }
+                x += 1;
+            }
+            y += 1;
+        }
+
+        Self::from_shape(shape)
+    }
+
+    /// Same as `from_rows` for a grid of booleans, `true` being a cell of the
+    /// figure.
+    pub fn from_grid(grid: &[&[bool]]) -> Result<Self, ParseFigureError> {
+        let mut shape = 0u64;
+
+        for (y, row) in grid.iter().enumerate() {
+            for (x, _) in row.iter().enumerate().filter(|(_, &cell)| cell) {
+                if x >= FIGURE_SPAN as usize || y >= FIGURE_SPAN as usize {
+                    return Err(ParseFigureError::TooLarge);
+                }
+                shape |= 1 << (y * FIGURE_SPAN as usize + x);
+            }
+        }
+
+        if shape == 0 {
+            return Err(ParseFigureError::Empty);
+        }
+        Ok(Self::from_shape(shape))
+    }
+
+    // moves the cells of `shape` to the top left corner of the grid, deriving
+    // the bounding box and size from them.
+    pub(crate) const fn from_shape(mut shape: u64) -> Self {
+        assert!(shape != 0, "figures must have at least one cell");
+        const COLUMN: u64 = 0x0101_0101_0101_0101;
+
+        while shape & 0xff == 0 {
+            shape >>= FIGURE_SPAN;
+        }
+        while shape & COLUMN == 0 {
+            shape >>= 1;
+        }
+
+        // the columns in use are the union of every row.
+        let mut columns = 0u8;
+        let mut y = 0;
+        while y < FIGURE_SPAN {
+            columns |= (shape >> (y * FIGURE_SPAN)) as u8;
+            y += 1;
+        }
+        let width = (u8::BITS - columns.leading_zeros()) as u8;
+        let height = (u64::BITS - shape.leading_zeros()).div_ceil(FIGURE_SPAN as u32) as u8;
+
+        Self {
+            shape,
+            size: shape.count_ones() as u8,
+            width,
+            height,
+        }
+    }
+
+    /// Same figure turned 90 degrees clockwise.
+    pub fn rotated(&self) -> Self {
+        let mut shape = 0u64;
+        for (x, y) in self.cells() {
+            let (x, y) = (self.height - 1 - y, x);
+            shape |= 1 << (y * FIGURE_SPAN + x);
+        }
+
+        Self::from_shape(shape)
+    }
+
+    /// Same figure mirrored left to right.
+    pub fn reflected(&self) -> Self {
+        let mut shape = 0u64;
+        for (x, y) in self.cells() {
+            let x = self.width - 1 - x;
+            shape |= 1 << (y * FIGURE_SPAN + x);
+        }
+
+        Self::from_shape(shape)
+    }
+
+    /// `(x, y)` offsets of the cells of the figure, column by column.
+    pub fn cells(&self) -> impl Iterator<Item = (u8, u8)> + '_ {
+        (0..self.width)
+            .flat_map(|x| (0..self.height).map(move |y| (x, y)))
+            .filter(|&offsets| self.contains(offsets))
+    }
+
+    pub fn contains(&self, offsets: (u8, u8)) -> bool {
+        let (x, y) = offsets;
+        x < FIGURE_SPAN && y < FIGURE_SPAN && self.shape & 1 << (y * FIGURE_SPAN + x) != 0
+    }
+
+    /// Highest `(x, y)` offsets the figure can be placed at on a board of
+    /// `dims`, `None` if it does not fit at all.
+    pub fn max_offset(&self, dims: Dimensions) -> Option<(u8, u8)> {
+        let x = dims.columns.checked_sub(self.width)?;
+        let y = dims.rows.checked_sub(self.height)?;
+        Some((x, y))
+    }
+
+    /// Board cells covered by the figure placed at offsets `(0, 0)`, shifting
+    /// it right by an action places it at that action's offsets.
+    pub fn value<B: Board>(&self, dims: Dimensions) -> B {
+        if self.max_offset(dims).is_none() {
+            return B::EMPTY;
+        }
+
+        self.cells().fold(B::EMPTY, |value, offsets| value | dims.mask(offsets))
+    }
+}
+
+/// Ways a figure may be turned before placing it, a rule of the game.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Orientations {
+    /// Figures are placed as drawn.
+    #[default]
+    Fixed,
+    /// Figures may be rotated by multiples of 90 degrees.
+    Rotations,
+    /// Figures may be rotated and mirrored.
+    RotationsAndReflections,
+}
+
+impl Orientations {
+    /// Distinct orientations of `figure` allowed by the rule, `figure` itself
+    /// being always the first one.
+    pub fn variants(&self, figure: &Figure) -> Vec<Figure> {
+        let mut bases = vec![*figure];
+        if *self == Self::RotationsAndReflections {
+            bases.push(figure.reflected());
+        }
+
+        let turns = if *self == Self::Fixed { 1 } else { 4 };
+        let mut variants: Vec<Figure> = Vec::with_capacity(bases.len() * turns);
+        for base in bases {
+            let mut variant = base;
+            for _ in 0..turns {
+                if !variants.contains(&variant) {
+                    variants.push(variant);
+                }
+                variant = variant.rotated();
+            }
+        }
+
+        variants
+    }
+
+    pub fn is_fixed(&self) -> bool {
+        *self == Self::Fixed
+    }
 }
 
 pub const FIGURES: [Figure; TOTAL_FIGURES] = [
-    Figure {
-        value: 0b1000_0000_0000_0000_0000_0000, // 0b100000_000000_000000_000000,
-        size: 1,
-        max_offset: (5, 3),
-    },
-    Figure {
-        value: 0b1110_0000_0000_0000_0000_0000, //0b100000_100000_100000_000000,
-        size: 3,
-        max_offset: (5, 1),
-    },
-    Figure {
-        value: 0b1100_0100_0000_0000_0000_0000, //0b100000_110000_000000_000000,
-        size: 3,
-        max_offset: (4, 2),
-    },
-    Figure {
-        value: 0b1000_1100_0000_0000_0000_0000, //0b110000_010000_000000_000000,
-        size: 3,
-        max_offset: (4, 2),
-    },
-    Figure {
-        value: 0b1100_1100_0000_0000_0000_0000, //0b110000_110000_000000_000000,
-        size: 4,
-        max_offset: (4, 2),
-    },
-    Figure {
-        value: 0b1000_1100_0100_0000_0000_0000, //0b110000_011000_000000_000000,
-        size: 4,
-        max_offset: (3, 2),
-    },
+    Figure::from_rows(&["#"]),
+    Figure::from_rows(&[
+        "#",
+        "#",
+        "#",
+    ]),
+    Figure::from_rows(&[
+        "#.",
+        "##",
+    ]),
+    Figure::from_rows(&[
+        "##",
+        ".#",
+    ]),
+    Figure::from_rows(&[
+        "##",
+        "##",
+    ]),
+    Figure::from_rows(&[
+        "##.",
+        ".##",
+    ]),
 ];
 
+/// Error returned when parsing the grid notation of a `Figure`. Rows and
+/// columns are 1-based.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseFigureError {
+    Empty,
+    TooLarge,
+    InvalidCell { row: usize, column: usize, found: char },
+}
+
+impl fmt::Display for ParseFigureError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "figure has no cells"),
+            Self::TooLarge => {
+                write!(f, "figure does not fit in {}x{} cells", FIGURE_SPAN, FIGURE_SPAN)
+            }
+            Self::InvalidCell { row, column, found } => write!(
+                f,
+                "row {}, column {}: expected `#` or `.`, found `{}`",
+                row, column, found
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ParseFigureError {}
+
+/// Grid notation of a figure: the rows of its bounding box from top to
+/// bottom, `#` for the cells of the figure and `.` otherwise, separated by
+/// `/` or line breaks, e.g. `##./.##`. Rows may have different lengths.
+impl fmt::Display for Figure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for y in 0..self.height {
+            if y > 0 {
+                write!(f, "/")?;
+            }
+            for x in 0..self.width {
+                write!(f, "{}", if self.contains((x, y)) { '#' } else { '.' })?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for Figure {
+    type Err = ParseFigureError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut grid = Vec::new();
+
+        for (y, row) in s.trim().split(['/', '\n']).enumerate() {
+            let cells = row
+                .trim()
+                .chars()
+                .enumerate()
+                .map(|(x, c)| match c {
+                    '#' => Ok(true),
+                    '.' => Ok(false),
+                    _ => Err(ParseFigureError::InvalidCell {
+                        row: y + 1,
+                        column: x + 1,
+                        found: c,
+                    }),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            grid.push(cells);
+        }
+
+        let rows = grid.iter().map(Vec::as_slice).collect::<Vec<_>>();
+        Self::from_grid(&rows)
+    }
+}
 
-#[derive(Clone, Copy)]
-pub struct Jigsaw {
-    pub board: u32,
+/// Probability of drawing each figure of a set, `FIGURES` by default, at the
+/// start of a round.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FigureDistribution {
+    probabilities: Vec<f32>,
+}
+
+impl FigureDistribution {
+    /// Builds the distribution from non-negative weights, one per figure,
+    /// which do not need to add up to one.
+    pub fn new(weights: &[f32]) -> Self {
+        assert!(weights.iter().all(|w| w.is_finite() && *w >= 0.0));
+        let total = weights.iter().sum::<f32>();
+        assert!(total > 0.0);
+
+        Self {
+            probabilities: weights.iter().map(|w| w / total).collect(),
+        }
+    }
+
+    /// Whether `new` accepts `weights`.
+    pub fn is_valid(weights: &[f32]) -> bool {
+        weights.iter().all(|w| w.is_finite() && *w >= 0.0) && weights.iter().sum::<f32>() > 0.0
+    }
+
+    // probabilities read back from a table file are already normalized, so
+    // they are kept bit for bit.
+    pub(crate) fn from_probabilities(probabilities: Vec<f32>) -> Self {
+        Self { probabilities }
+    }
+
+    pub fn uniform(figures: usize) -> Self {
+        Self::new(&vec![1.0; figures])
+    }
+
+    pub fn probability(&self, figure: u8) -> f32 {
+        self.probabilities[figure as usize]
+    }
+
+    pub fn probabilities(&self) -> &[f32] {
+        &self.probabilities
+    }
+
+    pub fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> u8 {
+        let index = WeightedIndex::new(&self.probabilities).unwrap();
+        index.sample(rng) as u8
+    }
+}
+
+impl Default for FigureDistribution {
+    fn default() -> Self {
+        Self::uniform(TOTAL_FIGURES)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "serde_impl::RawJigsaw<B>"))]
+pub struct Jigsaw<B: Board = u32> {
+    /// Cells filled by the figures placed so far.
+    pub board: B,
+    /// Cells blocked from the start of the game. They are never part of
+    /// `board` but count as occupied, see `occupied`.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "serde_impl::is_empty"))]
+    pub obstacles: B,
     pub figure: u8,
     pub round: u8,
+    pub dims: Dimensions,
+    /// Figures `figure` indexes into, see `FigureSet`. They are not part of
+    /// the serialized game: deserialized games always draw `FIGURES`, so
+    /// games of custom figure sets cannot be read back.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub figures: &'static [Figure],
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Orientations::is_fixed"))]
+    pub orientations: Orientations,
+}
+
+/// Move of a round: placing the current figure with the top left corner of
+/// its bounding box at column `x` and row `y`, or skipping it.
+///
+/// Tables and file formats number actions instead, see `index`. With the
+/// `serde` feature actions are written as their `offsets`, `[x, y]` or `null`
+/// when skipping, which do not depend on the board.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(from = "Option<(u8, u8)>", into = "Option<(u8, u8)>"))]
+pub enum Action {
+    Place { x: u8, y: u8 },
+    #[default]
+    Skip,
 }
 
+impl Action {
+    /// Action numbered `index` on a board of `dims`, the cells column by
+    /// column followed by skipping, `None` past the skip action.
+    pub fn from_index(index: u8, dims: Dimensions) -> Option<Self> {
+        match index.cmp(&dims.skip_action()) {
+            std::cmp::Ordering::Less => {
+                let (x, y) = dims.action_to_offsets(index);
+                Some(Action::Place { x, y })
+            }
+            std::cmp::Ordering::Equal => Some(Action::Skip),
+            std::cmp::Ordering::Greater => None,
+        }
+    }
+
+    /// Number of the action on a board of `dims`, see `from_index`. The
+    /// offsets of a placement must lie on the board.
+    pub fn index(&self, dims: Dimensions) -> u8 {
+        match *self {
+            Action::Place { x, y } => {
+                assert!(x < dims.columns && y < dims.rows);
+                dims.offset_to_action((x, y))
+            }
+            Action::Skip => dims.skip_action(),
+        }
+    }
+
+    /// Column and row of the top left corner of the figure, `None` when
+    /// skipping.
+    pub fn offsets(&self) -> Option<(u8, u8)> {
+        match *self {
+            Action::Place { x, y } => Some((x, y)),
+            Action::Skip => None,
+        }
+    }
+
+    pub fn is_skip(&self) -> bool {
+        *self == Action::Skip
+    }
+}
+
+impl From<(u8, u8)> for Action {
+    fn from((x, y): (u8, u8)) -> Self {
+        Action::Place { x, y }
+    }
+}
+
+/// Action together with the orientation of the figure, an index into
+/// `Jigsaw::variants`. Skipping ignores the orientation.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Placement {
+    pub orientation: u8,
+    pub action: Action,
+}
+
+impl<B: Board> Jigsaw<B> {
+    pub fn new(dims: Dimensions) -> Self {
+        assert!(dims.fits::<B>());
+        Self {
+            board: B::EMPTY,
+            obstacles: B::EMPTY,
+            figure: 0,
+            round: 0,
+            dims,
+            figures: &FIGURES,
+            orientations: Orientations::Fixed,
+        }
+    }
 
-impl Jigsaw {
-    pub fn set_random_figure(&mut self, rng: &mut rand::rngs::StdRng) {
-        self.figure = rng.gen_range(0..FIGURES.len()) as u8;
+    pub fn set_random_figure(&mut self, rng: &mut rand::rngs::StdRng, figures: &FigureDistribution) {
+        debug_assert_eq!(figures.probabilities().len(), self.figures.len());
+        self.figure = figures.sample(rng);
     }
 
     pub fn has_finished(&self) -> bool {
-        self.board == TERMINAL_STATE
+        self.occupied() == self.dims.terminal_state()
+    }
+
+    /// Cells that cannot be filled anymore, placed figures and obstacles.
+    /// Solvers treat obstacles as cells filled before the game started.
+    pub fn occupied(&self) -> B {
+        self.board | self.obstacles
     }
 
-    pub fn perform_action(&mut self, action: u8) {        
-        if action != SKIP_ACTION {
+    /// Cells left to fill, obstacles excluded.
+    pub fn empty_cells(&self) -> u32 {
+        self.dims.cells() as u32 - self.occupied().count_ones()
+    }
+
+    pub fn perform_action(&mut self, action: Action) {
+        if let Action::Place { .. } = action {
             assert!(self.is_legal(action));
-            self.board |= self.figure().value >> action;
+            self.board |= self.figure().value::<B>(self.dims) >> action.index(self.dims);
         }
 
         self.round += 1;
     }
 
     pub fn figure(&self) -> &Figure {
-        &FIGURES[self.figure as usize]
+        &self.figures[self.figure as usize]
     }
 
-    fn mask(offsets: (u8, u8)) -> u32 {
-        (1 << (TOTAL_CELLS - 1)) >> Self::offset_to_action(offsets)
+    pub fn get_value(&self, offsets: (u8, u8)) -> bool {
+        let mask = self.dims.mask::<B>(offsets);
+        (self.board & mask) != B::EMPTY
     }
 
-    pub fn get_value(&self, offsets: (u8, u8)) -> bool {
-        let mask = Self::mask(offsets);
-        (self.board & mask) != 0
+    pub fn is_obstacle(&self, offsets: (u8, u8)) -> bool {
+        let mask = self.dims.mask::<B>(offsets);
+        (self.obstacles & mask) != B::EMPTY
     }
 
+    /// Fills or empties a cell, an obstacle there becoming an empty cell.
     pub fn toggle(&mut self, offsets: (u8, u8)) {
-        let mask = Self::mask(offsets);
-        self.board ^= mask;
+        let mask = self.dims.mask(offsets);
+        if self.obstacles & mask != B::EMPTY {
+            self.obstacles ^= mask;
+        } else {
+            self.board ^= mask;
+        }
     }
 
-    pub fn is_legal(&self, action: u8) -> bool {
-        if action == SKIP_ACTION { return true; }
+    /// Blocks or unblocks a cell, a filled cell becoming an obstacle.
+    pub fn toggle_obstacle(&mut self, offsets: (u8, u8)) {
+        let mask = self.dims.mask::<B>(offsets);
+        self.board &= !mask;
+        self.obstacles ^= mask;
+    }
 
-        let (x_offset, y_offset) = Jigsaw::action_to_offsets(action);
-        assert!(x_offset < M && y_offset < N);
-        let figure = self.figure();
-        
-        let mut ilegal = false;
+    pub fn is_legal(&self, action: Action) -> bool {
+        self.is_legal_figure(self.figure(), action)
+    }
 
-        // here we try not breaking the CPU pipeline since data is probably already
-        // in cache and these operations are blazingly fast in any CPU, thus not
-        // breaking the pipeline could help us to get more performance rather than
-        // using less instructions.
-        ilegal |= x_offset > figure.max_offset.0;
-        ilegal |= y_offset > figure.max_offset.1;
-        ilegal |= (self.board & figure.value >> (x_offset * N + y_offset)) != 0;
+    // legality of placing `figure`, which may be any orientation of the
+    // current figure.
+    fn is_legal_figure(&self, figure: &Figure, action: Action) -> bool {
+        let Action::Place { x: x_offset, y: y_offset } = action else {
+            return true;
+        };
+        let Some(max_offset) = figure.max_offset(self.dims) else {
+            return false;
+        };
 
-        !ilegal
+        // offsets past the maximum would shift the figure out of the board,
+        // or by more bits than the board has, so they are rejected before
+        // checking the overlap.
+        if x_offset > max_offset.0 || y_offset > max_offset.1 {
+            return false;
+        }
+        let index = self.dims.offset_to_action((x_offset, y_offset));
+        (self.occupied() & figure.value::<B>(self.dims) >> index) == B::EMPTY
     }
 
-    pub fn legal_actions(&self) -> Vec<u8> {
-        (0..TOTAL_ACTIONS).filter(|&x| self.is_legal(x)).collect()
+    /// Every place the current figure fits at, column by column, followed by
+    /// skipping.
+    pub fn legal_actions(&self) -> Vec<Action> {
+        self.actions().filter(|&action| self.is_legal(action)).collect()
     }
 
-    pub fn action_to_offsets(action: u8) -> (u8, u8) {
-        // given an action in range [0, SKIP_ACTION] represented as 
-        // 0bXXXYY in binary, we can use the 2 LSB to get x-offset
-        // and the 3 MSB to get y-offset.
-        // Note: this does not work for all board sizes, but it does
-        // work for fishing-jigsaw board, which has size (4, 6).
-        let (x_offset, y_offset) = (action >> 2, action & 0b11);
-        (x_offset, y_offset)
+    // every action of the board, in index order.
+    fn actions(&self) -> impl Iterator<Item = Action> + '_ {
+        (0..=self.dims.skip_action()).map(|index| Action::from_index(index, self.dims).unwrap())
     }
 
-    pub fn offset_to_action(offsets: (u8, u8)) -> u8 {
-        offsets.0 * N + offsets.1
+    /// Orientations the current figure may be placed in, see `Orientations`.
+    pub fn variants(&self) -> Vec<Figure> {
+        self.orientations.variants(self.figure())
     }
 
-    pub fn fig_intesect(&self, action: u8, offsets: (u8, u8)) -> bool {
-        let f = self.figure().value >> action;
-        let m = Self::mask(offsets);
-        (f & m) != 0
+    pub fn is_legal_placement(&self, placement: Placement) -> bool {
+        let variants = self.variants();
+        match variants.get(placement.orientation as usize) {
+            Some(figure) => self.is_legal_figure(figure, placement.action),
+            None => false,
+        }
     }
-}
 
-#[cfg(test)]
-mod jigsaw_test {
-    use super::*;
-
-    #[test]
-    fn test_skip_action() {
-        let mut state = Jigsaw::default();
+    /// Legal placements in every orientation, skipping last. Placing the
+    /// figure as drawn comes first, as `legal_actions` does.
+    pub fn legal_placements(&self) -> Vec<Placement> {
+        let mut placements = self
+            .variants()
+            .iter()
+            .enumerate()
+            .flat_map(|(orientation, figure)| {
+                self.actions()
+                    .filter(move |&action| !action.is_skip() && self.is_legal_figure(figure, action))
+                    .map(move |action| Placement {
+                        orientation: orientation as u8,
+                        action,
+                    })
+            })
+            .collect::<Vec<_>>();
 
-        assert_eq!(state.round, 0);
-        state.perform_action(SKIP_ACTION);
-        assert_eq!(state.round, 1);
+        placements.push(Placement {
+            orientation: 0,
+            action: Action::Skip,
+        });
+        placements
     }
 
-    #[test]
-    fn test_overlap() {
-        let mut state = Jigsaw::default();
-        state.figure = 1;
+    pub fn perform_placement(&mut self, placement: Placement) {
+        if let Action::Place { .. } = placement.action {
+            assert!(self.is_legal_placement(placement));
+            let figure = self.variants()[placement.orientation as usize];
+            self.board |= figure.value::<B>(self.dims) >> placement.action.index(self.dims);
+        }
 
-        assert!(state.is_legal(0));
-        state.perform_action(0);
+        self.round += 1;
+    }
 
-        state.figure = 0;
-        assert!(!state.is_legal(0));
-        assert!(!state.is_legal(1));
-        assert!(!state.is_legal(2));
+    pub fn fig_intesect(&self, action: Action, offsets: (u8, u8)) -> bool {
+        let Action::Place { .. } = action else {
+            return false;
+        };
 
-        assert!(state.is_legal(3));
+        let f = self.figure().value::<B>(self.dims) >> action.index(self.dims);
+        let m = self.dims.mask::<B>(offsets);
+        (f & m) != B::EMPTY
     }
 }
 
-impl fmt::Debug for Jigsaw {
+impl<B: Board> fmt::Debug for Jigsaw<B> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         writeln!(f, "<--------{}-------->", self.round)?;
         
-        for i in 0..N {
-            // write board first row
-            let mut mask = 1 << (N * M - (i + 1));
-            for j in 0..M {
-                let action = i + j * N;
+        for y in 0..self.dims.rows {
+            // write board row
+            for x in 0..self.dims.columns {
+                let action = Action::Place { x, y };
 
-                let value = (self.board & mask) != 0;
+                let value = self.get_value((x, y));
                 assert!(!(value && self.is_legal(action)));
-                if value {
+                if self.is_obstacle((x, y)) {
+                    write!(f, "⬛")?;
+                }
+                else if value {
                     write!(f, "🟥")?;
                 }
                 else if self.is_legal(action) {
@@ -190,36 +726,619 @@ impl fmt::Debug for Jigsaw {
                 else {
                     write!(f, "🟨")?;
                 }
-                mask >>= N;
             }
             
-            // write figure first row
-            let mut mask = 1 << (N * M - (i + 1));
-            for _ in 0..M {
-                let value = (self.figure().value & mask) != 0;
-                if value {
+            // write figure row
+            for x in 0..self.dims.columns {
+                if self.figure().contains((x, y)) {
                     write!(f, "🔳")?;
                 }
                 else {
                     write!(f, "  ")?;
                 }
-                mask >>= N;
             }
 
-            write!(f, "\n")?;
+            writeln!(f)?;
         }
         
         Ok(())
     }
 }
 
-impl Default for Jigsaw {
+impl<B: Board> Default for Jigsaw<B> {
     fn default() -> Self {
-        Self {
-            board: INIT_STATE,
-            figure: 0,
-            round: 0,
+        Self::new(STANDARD)
+    }
+}
+
+/// Error returned when parsing the text notation of a `Jigsaw`. Rows and
+/// columns are 1-based, as they would be read on screen.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseJigsawError {
+    MissingRows,
+    RowLength { row: usize, expected: usize, found: usize },
+    InvalidSize { rows: usize, columns: usize, max_cells: u32 },
+    InvalidCell { row: usize, column: usize, found: char },
+    InvalidFigure(String),
+    InvalidRound(String),
+    TrailingInput(String),
+}
+
+impl fmt::Display for ParseJigsawError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::MissingRows => write!(f, "expected at least one row"),
+            Self::RowLength { row, expected, found } => {
+                write!(f, "row {}: expected {} cells, found {}", row, expected, found)
+            }
+            Self::InvalidSize { rows, columns, max_cells } => write!(
+                f,
+                "a {}x{} board must have between 1 and {} cells",
+                rows, columns, max_cells
+            ),
+            Self::InvalidCell { row, column, found } => write!(
+                f,
+                "row {}, column {}: expected `#`, `X` or `.`, found `{}`",
+                row, column, found
+            ),
+            Self::InvalidFigure(s) => {
+                write!(f, "invalid figure `{}`", s)
+            }
+            Self::InvalidRound(s) => write!(f, "invalid round `{}`", s),
+            Self::TrailingInput(s) => write!(f, "unexpected `{}` after round", s),
         }
     }
 }
 
+impl std::error::Error for ParseJigsawError {}
+
+/// Compact notation of the game state: the board rows from top to bottom
+/// with `#` for filled, `X` for obstacles and `.` for empty cells, separated
+/// by `/`, followed by the figure index and the round, e.g.
+/// `##..../#...../....X./...... 2 3`.
+///
+/// The alternate form `{:#}` writes one row per line instead, and both forms
+/// are accepted by `FromStr`. The board dimensions are taken from the number
+/// and length of the rows.
+impl<B: Board> fmt::Display for Jigsaw<B> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let separator = if f.alternate() { "\n" } else { "/" };
+
+        for y in 0..self.dims.rows {
+            if y > 0 {
+                write!(f, "{}", separator)?;
+            }
+            for x in 0..self.dims.columns {
+                let c = if self.is_obstacle((x, y)) {
+                    'X'
+                } else if self.get_value((x, y)) {
+                    '#'
+                } else {
+                    '.'
+                };
+                write!(f, "{}", c)?;
+            }
+        }
+
+        let separator = if f.alternate() { "\n" } else { " " };
+        write!(f, "{}{} {}", separator, self.figure, self.round)
+    }
+}
+
+impl<B: Board> FromStr for Jigsaw<B> {
+    type Err = ParseJigsawError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse_with(s, &FIGURES)
+    }
+}
+
+impl<B: Board> Jigsaw<B> {
+    /// Same as `FromStr`, the figure index being checked against `figures`
+    /// instead of `FIGURES`.
+    pub fn parse_with(s: &str, figures: &'static [Figure]) -> Result<Self, ParseJigsawError> {
+        let mut tokens = s.split_whitespace().peekable();
+
+        // rows are every leading token, up to the figure index.
+        let rows: Vec<&str> = match tokens.peek() {
+            Some(token) if token.contains('/') => tokens.next().unwrap().split('/').collect(),
+            _ => std::iter::from_fn(|| {
+                tokens.next_if(|token| !token.starts_with(|c: char| c.is_ascii_digit()))
+            })
+            .collect(),
+        };
+        let Some(first) = rows.first() else {
+            return Err(ParseJigsawError::MissingRows);
+        };
+
+        let columns = first.chars().count();
+        for (y, row) in rows.iter().enumerate() {
+            let found = row.chars().count();
+            if found != columns {
+                return Err(ParseJigsawError::RowLength { row: y + 1, expected: columns, found });
+            }
+        }
+        let dims = u8::try_from(rows.len())
+            .ok()
+            .zip(u8::try_from(columns).ok())
+            .filter(|&(rows, columns)| Dimensions::is_valid(rows, columns))
+            .map(|(rows, columns)| Dimensions::new(rows, columns))
+            .filter(Dimensions::fits::<B>)
+            .ok_or(ParseJigsawError::InvalidSize {
+                rows: rows.len(),
+                columns,
+                max_cells: B::BITS,
+            })?;
+
+        let mut game = Jigsaw::new(dims);
+        game.figures = figures;
+        for (y, row) in rows.iter().enumerate() {
+            for (x, c) in row.chars().enumerate() {
+                match c {
+                    '#' => game.toggle((x as u8, y as u8)),
+                    'X' => game.toggle_obstacle((x as u8, y as u8)),
+                    '.' => {}
+                    _ => {
+                        return Err(ParseJigsawError::InvalidCell {
+                            row: y + 1,
+                            column: x + 1,
+                            found: c,
+                        })
+                    }
+                }
+            }
+        }
+
+        if let Some(token) = tokens.next() {
+            game.figure = token
+                .parse()
+                .ok()
+                .filter(|&figure: &u8| (figure as usize) < figures.len())
+                .ok_or_else(|| ParseJigsawError::InvalidFigure(token.to_string()))?;
+        }
+
+        if let Some(token) = tokens.next() {
+            game.round = token
+                .parse()
+                .map_err(|_| ParseJigsawError::InvalidRound(token.to_string()))?;
+        }
+
+        match tokens.next() {
+            Some(token) => Err(ParseJigsawError::TrailingInput(token.to_string())),
+            None => Ok(game),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+mod serde_impl {
+    use super::*;
+
+    pub fn is_empty<B: Board>(board: &B) -> bool {
+        *board == B::EMPTY
+    }
+
+    // deserialized states go through the same checks as the text notation,
+    // so out of range boards or figures are rejected up front.
+    impl TryFrom<String> for Figure {
+        type Error = ParseFigureError;
+
+        fn try_from(grid: String) -> Result<Self, Self::Error> {
+            grid.parse()
+        }
+    }
+
+    impl From<Figure> for String {
+        fn from(figure: Figure) -> Self {
+            figure.to_string()
+        }
+    }
+
+    impl From<Option<(u8, u8)>> for Action {
+        fn from(offsets: Option<(u8, u8)>) -> Self {
+            offsets.map_or(Action::Skip, Action::from)
+        }
+    }
+
+    impl From<Action> for Option<(u8, u8)> {
+        fn from(action: Action) -> Self {
+            action.offsets()
+        }
+    }
+
+    #[derive(serde::Deserialize)]
+    pub struct RawJigsaw<B> {
+        board: B,
+        #[serde(default)]
+        obstacles: B,
+        figure: u8,
+        round: u8,
+        #[serde(default)]
+        dims: Dimensions,
+        #[serde(default)]
+        orientations: Orientations,
+    }
+
+    impl<B: Board> TryFrom<RawJigsaw<B>> for Jigsaw<B> {
+        type Error = String;
+
+        fn try_from(raw: RawJigsaw<B>) -> Result<Self, Self::Error> {
+            let dims = raw.dims;
+            if !Dimensions::is_valid(dims.rows, dims.columns) || !dims.fits::<B>() {
+                return Err(format!(
+                    "a {}x{} board must have between 1 and {} cells",
+                    dims.rows,
+                    dims.columns,
+                    B::BITS
+                ));
+            }
+            if raw.board & !dims.terminal_state::<B>() != B::EMPTY {
+                return Err(format!("board {:?} has more than {} cells", raw.board, dims.cells()));
+            }
+            if raw.obstacles & !dims.terminal_state::<B>() != B::EMPTY {
+                return Err(format!("obstacles {:?} have more than {} cells", raw.obstacles, dims.cells()));
+            }
+            if raw.board & raw.obstacles != B::EMPTY {
+                return Err("board and obstacles overlap".to_string());
+            }
+            if raw.figure as usize >= TOTAL_FIGURES {
+                return Err(format!("figure {} is not lower than {}", raw.figure, TOTAL_FIGURES));
+            }
+
+            Ok(Jigsaw {
+                board: raw.board,
+                obstacles: raw.obstacles,
+                figure: raw.figure,
+                round: raw.round,
+                dims,
+                figures: &FIGURES,
+                orientations: raw.orientations,
+            })
+        }
+    }
+
+    #[derive(serde::Deserialize)]
+    pub struct RawDimensions {
+        rows: u8,
+        columns: u8,
+    }
+
+    impl TryFrom<RawDimensions> for Dimensions {
+        type Error = String;
+
+        fn try_from(raw: RawDimensions) -> Result<Self, Self::Error> {
+            if !Dimensions::is_valid(raw.rows, raw.columns) {
+                return Err(format!(
+                    "a {}x{} board must have between 1 and {} cells",
+                    raw.rows,
+                    raw.columns,
+                    MAX_CELLS
+                ));
+            }
+
+            Ok(Dimensions::new(raw.rows, raw.columns))
+        }
+    }
+}
+
+#[cfg(test)]
+mod jigsaw_test {
+    use super::*;
+
+    #[test]
+    fn test_skip_action() {
+        let mut state: Jigsaw = Jigsaw::default();
+
+        assert_eq!(state.round, 0);
+        state.perform_action(Action::Skip);
+        assert_eq!(state.round, 1);
+        assert_eq!(state.board, INIT_STATE);
+    }
+
+    #[test]
+    fn test_action_index() {
+        assert_eq!(Action::from_index(7, STANDARD), Some(Action::Place { x: 1, y: 3 }));
+        assert_eq!(Action::from_index(SKIP_ACTION, STANDARD), Some(Action::Skip));
+        assert_eq!(Action::from_index(SKIP_ACTION + 1, STANDARD), None);
+
+        for index in 0..TOTAL_ACTIONS {
+            assert_eq!(Action::from_index(index, STANDARD).unwrap().index(STANDARD), index);
+        }
+        assert_eq!(Action::from((2, 1)).offsets(), Some((2, 1)));
+        assert!(Action::default().is_skip());
+
+        // placements off the board are never legal.
+        let state: Jigsaw = Jigsaw::default();
+        assert!(!state.is_legal(Action::Place { x: 6, y: 0 }));
+        assert!(!state.is_legal(Action::Place { x: 0, y: 4 }));
+    }
+
+    #[test]
+    fn test_overlap() {
+        let mut state: Jigsaw = Jigsaw {
+            figure: 1,
+            ..Default::default()
+        };
+
+        assert!(state.is_legal((0, 0).into()));
+        state.perform_action((0, 0).into());
+
+        state.figure = 0;
+        assert!(!state.is_legal((0, 0).into()));
+        assert!(!state.is_legal((0, 1).into()));
+        assert!(!state.is_legal((0, 2).into()));
+
+        assert!(state.is_legal((0, 3).into()));
+    }
+
+    #[test]
+    fn test_notation_round_trip() {
+        let state: Jigsaw = "###.../#...../....../.....# 2 5".parse().unwrap();
+        assert_eq!(state.board, 0b1100_1000_1000_0000_0000_0001);
+        assert_eq!((state.figure, state.round), (2, 5));
+
+        assert_eq!(state.to_string(), "###.../#...../....../.....# 2 5");
+        assert_eq!(format!("{:#}", state).parse::<Jigsaw>().unwrap().board, state.board);
+        assert_eq!("....../....../....../......".parse::<Jigsaw>().unwrap().board, INIT_STATE);
+    }
+
+    #[test]
+    fn test_notation_errors() {
+        assert_eq!(
+            "2 3".parse::<Jigsaw>().err(),
+            Some(ParseJigsawError::MissingRows)
+        );
+        assert_eq!(
+            "###.../#..../....../......".parse::<Jigsaw>().err(),
+            Some(ParseJigsawError::RowLength { row: 2, expected: 6, found: 5 })
+        );
+        assert_eq!(
+            "......../......../......../......../........".parse::<Jigsaw>().err(),
+            Some(ParseJigsawError::InvalidSize { rows: 5, columns: 8, max_cells: 32 })
+        );
+        assert_eq!(
+            "###.../#...../...x../......".parse::<Jigsaw>().err(),
+            Some(ParseJigsawError::InvalidCell { row: 3, column: 4, found: 'x' })
+        );
+        assert_eq!(
+            "....../....../....../...... 6".parse::<Jigsaw>().err(),
+            Some(ParseJigsawError::InvalidFigure("6".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_dimensions() {
+        let dims = Dimensions::new(5, 5);
+        assert_eq!(dims.terminal_state::<u32>(), (1 << 25) - 1);
+        assert_eq!(dims.action_to_offsets(7), (1, 2));
+        assert_eq!(dims.offset_to_action((1, 2)), 7);
+
+        let state: Jigsaw = "#..../#..../#..../...../..... 1 0".parse().unwrap();
+        assert_eq!(state.dims, dims);
+        assert_eq!(state.to_string(), "#..../#..../#..../...../..... 1 0");
+        assert_eq!(state.board, FIGURES[1].value::<u32>(dims));
+
+        // the long piece fits in the fourth column of a 4x8 board.
+        let mut state: Jigsaw = Jigsaw::new(Dimensions::new(4, 8));
+        state.figure = 5;
+        assert_eq!(FIGURES[5].max_offset(state.dims), Some((5, 2)));
+        assert!(state.is_legal(Action::from((5, 2))));
+        assert!(!state.is_legal(Action::from((6, 0))));
+        assert_eq!(state.legal_actions().len(), 6 * 3 + 1);
+
+        state.dims = Dimensions::new(1, 8);
+        assert_eq!(state.legal_actions(), vec![Action::Skip]);
+    }
+
+    #[test]
+    fn test_standard_figures() {
+        let values = [
+            0b1000 << 20,
+            0b1110 << 20,
+            0b1100_0100 << 16,
+            0b1000_1100 << 16,
+            0b1100_1100 << 16,
+            0b1000_1100_0100 << 12,
+        ];
+        let sizes = [1, 3, 3, 3, 4, 4];
+        let max_offsets = [(5, 3), (5, 1), (4, 2), (4, 2), (4, 2), (3, 2)];
+
+        for (i, figure) in FIGURES.iter().enumerate() {
+            assert_eq!(figure.value::<u32>(STANDARD), values[i]);
+            assert_eq!(figure.size, sizes[i]);
+            assert_eq!(figure.max_offset(STANDARD), Some(max_offsets[i]));
+            assert_eq!(figure.to_string().parse::<Figure>(), Ok(*figure));
+        }
+    }
+
+    #[test]
+    fn test_obstacles() {
+        let mut state: Jigsaw = "X...../#...../....../...... 4 0".parse().unwrap();
+        assert_eq!(state.obstacles, 1 << (TOTAL_CELLS - 1));
+        assert_eq!(state.board, 1 << (TOTAL_CELLS - 2));
+        assert_eq!(state.empty_cells(), 22);
+        assert!(!state.is_legal((0, 0).into()));
+        assert!(state.is_legal((0, 2).into()));
+        assert_eq!(state.to_string(), "X...../#...../....../...... 4 0");
+
+        state.toggle((0, 0));
+        assert_eq!(state.obstacles, 0);
+        state.toggle_obstacle((0, 1));
+        assert_eq!(state.to_string(), "....../X...../....../...... 4 0");
+        assert_eq!(state.board, 0);
+
+        let state: Jigsaw = "XXXXXX/XXXXXX/XXXXXX/XXXXX. 0 3".parse().unwrap();
+        assert!(!state.has_finished());
+        assert_eq!(state.legal_actions(), vec![Action::Place { x: 5, y: 3 }, Action::Skip]);
+    }
+
+    #[test]
+    fn test_orientations() {
+        let count = |rule: Orientations| FIGURES.map(|f| rule.variants(&f).len());
+        assert_eq!(count(Orientations::Fixed), [1; TOTAL_FIGURES]);
+        assert_eq!(count(Orientations::Rotations), [1, 2, 4, 4, 1, 2]);
+        assert_eq!(count(Orientations::RotationsAndReflections), [1, 2, 4, 4, 1, 4]);
+
+        assert_eq!(FIGURES[1].rotated().to_string(), "###");
+        assert_eq!(FIGURES[2].rotated().to_string(), "##/#.");
+        assert_eq!(FIGURES[2].reflected().to_string(), ".#/##");
+        assert_eq!(FIGURES[5].reflected().to_string(), ".##/##.");
+        assert_eq!(FIGURES[5].rotated().rotated(), FIGURES[5]);
+
+        let mut state: Jigsaw = Jigsaw {
+            figure: 1,
+            orientations: Orientations::Rotations,
+            ..Default::default()
+        };
+        let legal = state.legal_placements();
+        // 6 * 2 positions standing, 4 * 4 lying, and skipping.
+        assert_eq!(legal.len(), 12 + 16 + 1);
+        assert_eq!(legal[0], Placement { orientation: 0, action: (0, 0).into() });
+        assert_eq!(legal.last().unwrap().action, Action::Skip);
+        assert!(!state.is_legal_placement(Placement { orientation: 2, action: (0, 0).into() }));
+
+        state.perform_placement(Placement { orientation: 1, action: (0, 0).into() });
+        assert_eq!(state.to_string(), "###.../....../....../...... 1 1");
+    }
+
+    #[test]
+    fn test_figure_notation() {
+        let figure: Figure = "##./.##".parse().unwrap();
+        assert_eq!(figure, FIGURES[5]);
+        assert_eq!("\n  ##.\n  .##\n".parse::<Figure>(), Ok(FIGURES[5]));
+
+        // empty rows and columns around the figure are dropped.
+        let figure: Figure = "..../.#../.##./....".parse().unwrap();
+        assert_eq!(figure, FIGURES[2]);
+        assert_eq!((figure.width, figure.height), (2, 2));
+
+        // gaps inside the bounding box are kept.
+        let figure: Figure = "#.#".parse().unwrap();
+        assert_eq!((figure.size, figure.width, figure.height), (2, 3, 1));
+        assert_eq!(figure.to_string(), "#.#");
+        assert_eq!(Figure::from_grid(&[&[true, false, true]]), Ok(figure));
+
+        assert_eq!("../..".parse::<Figure>(), Err(ParseFigureError::Empty));
+        assert_eq!("#########".parse::<Figure>(), Err(ParseFigureError::TooLarge));
+        assert_eq!(
+            "##/#x".parse::<Figure>(),
+            Err(ParseFigureError::InvalidCell { row: 2, column: 2, found: 'x' })
+        );
+        // figures have no blocked cells.
+        let err = "#X".parse::<Figure>().unwrap_err();
+        assert_eq!(err.to_string(), "row 1, column 2: expected `#` or `.`, found `X`");
+    }
+
+    #[test]
+    fn test_figure_distribution() {
+        let figures = FigureDistribution::new(&[2.0, 0.0, 1.0, 0.0, 0.0, 1.0]);
+        assert_eq!(figures.probabilities(), &[0.5, 0.0, 0.25, 0.0, 0.0, 0.25]);
+
+        let mut rng = StdRng::seed_from_u64(2024);
+        let mut state: Jigsaw = Jigsaw::default();
+        for _ in 0..64 {
+            state.set_random_figure(&mut rng, &figures);
+            assert!(figures.probability(state.figure) > 0.0);
+        }
+    }
+
+    #[test]
+    fn test_wide_boards() {
+        let state: Jigsaw<u64> = "......../......../......../......../........ 5 0".parse().unwrap();
+        assert_eq!(state.dims, Dimensions::new(5, 8));
+        assert_eq!(state.legal_actions().len(), 6 * 4 + 1);
+
+        let mut state: Jigsaw<u128> = Jigsaw::new(Dimensions::new(8, 16));
+        state.figure = 4;
+        state.perform_action(Action::from((14, 6)));
+        assert_eq!(state.board.count_ones(), 4);
+        assert!(state.get_value((15, 7)));
+        assert!(!state.is_legal(Action::from((13, 5))));
+        assert!(state.is_legal(Action::from((12, 6))));
+    }
+
+    // replays random games on the standard board with every backing, which
+    // must agree on every legal action and resulting board.
+    #[test]
+    fn test_backings_agree() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let figures = FigureDistribution::default();
+
+        for _ in 0..200 {
+            let mut narrow: Jigsaw<u32> = Jigsaw::default();
+            let mut wide: Jigsaw<u64> = Jigsaw::default();
+            let mut widest: Jigsaw<u128> = Jigsaw::default();
+
+            while !narrow.has_finished() && narrow.round < 64 {
+                narrow.set_random_figure(&mut rng, &figures);
+                wide.figure = narrow.figure;
+                widest.figure = narrow.figure;
+
+                let actions = narrow.legal_actions();
+                assert_eq!(wide.legal_actions(), actions);
+                assert_eq!(widest.legal_actions(), actions);
+
+                let action = *actions.choose(&mut rng).unwrap();
+                narrow.perform_action(action);
+                wide.perform_action(action);
+                widest.perform_action(action);
+
+                assert_eq!(wide.board, narrow.board as u64);
+                assert_eq!(widest.board, narrow.board as u128);
+                assert_eq!(widest.to_string(), narrow.to_string());
+            }
+
+            assert_eq!(wide.has_finished(), narrow.has_finished());
+            assert_eq!(widest.has_finished(), narrow.has_finished());
+        }
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod jigsaw_serde_test {
+    use super::*;
+    use crate::deterministic::Deterministic;
+
+    #[test]
+    fn test_action_round_trip() {
+        let placement = Placement {
+            orientation: 1,
+            action: Action::Place { x: 2, y: 0 },
+        };
+        let json = serde_json::to_value(placement).unwrap();
+        assert_eq!(json, serde_json::json!({ "orientation": 1, "action": [2, 0] }));
+        assert_eq!(serde_json::from_value::<Placement>(json).unwrap(), placement);
+
+        let json = serde_json::to_string(&Action::Skip).unwrap();
+        assert_eq!(json, "null");
+        assert_eq!(serde_json::from_str::<Action>(&json).unwrap(), Action::Skip);
+
+        let dims = Dimensions::new(2, 2);
+        let mut strategy = Deterministic::with_dimensions(dims, FigureDistribution::default());
+        strategy.run();
+        let dsts = strategy.distances(0).collect::<Vec<_>>();
+        let json = serde_json::to_string(&dsts).unwrap();
+        assert_eq!(serde_json::from_str::<Vec<(Action, f32)>>(&json).unwrap(), dsts);
+    }
+
+    #[test]
+    fn test_dimensions() {
+        let json = r#"{ "rows": 3, "columns": 5 }"#;
+        assert_eq!(serde_json::from_str::<Dimensions>(json).unwrap(), Dimensions::new(3, 5));
+
+        assert!(serde_json::from_str::<Dimensions>(r#"{ "rows": 0, "columns": 5 }"#).is_err());
+        assert!(serde_json::from_str::<Dimensions>(r#"{ "rows": 12, "columns": 12 }"#).is_err());
+    }
+
+    #[test]
+    fn test_figure_grid() {
+        let figure = FIGURES[2];
+        let json = serde_json::to_string(&figure).unwrap();
+        assert_eq!(json, "\"#./##\"");
+        assert_eq!(serde_json::from_str::<Figure>(&json).unwrap(), figure);
+
+        assert!(serde_json::from_str::<Figure>("\"#X\"").is_err());
+        let json = r#"{ "shape": 1, "size": 4, "width": 2, "height": 2 }"#;
+        assert!(serde_json::from_str::<Figure>(json).is_err());
+    }
+}
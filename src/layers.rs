@@ -0,0 +1,69 @@
+use std::ops::ControlFlow;
+
+use crate::symmetry::Symmetries;
+use itertools::Itertools;
+use rayon::prelude::*;
+
+/// Table with a row of entries per canonical board, where the row of a board
+/// only depends on the rows of the boards it leads to, which have fewer empty
+/// cells. See `Deterministic` and `Tiered`.
+pub(crate) trait Layered: Sync {
+    type Entry: Copy + Default + Send;
+    /// Working space of `solve_board`, one per thread.
+    type Scratch: Default + Send;
+
+    fn symmetries(&self) -> &Symmetries;
+
+    fn cells(&self) -> u8;
+
+    /// Number of entries per board.
+    fn width(&self) -> usize;
+
+    /// Row of the canonical `board`, every board with fewer empty cells being
+    /// stored already.
+    fn solve_board(&self, board: u32, scratch: &mut Self::Scratch, row: &mut [Self::Entry]);
+
+    fn store(&mut self, board: u32, row: &[Self::Entry]);
+}
+
+/// Solves every canonical board but the full one, which must be stored
+/// already, by height, their number of empty cells. Boards of the same height
+/// do not depend on each other, so a layer is solved into a buffer, in
+/// parallel or not, and then stored. `progress` is called with the number of
+/// layers solved after each one of them, and returning `ControlFlow::Break`
+/// stops the build.
+pub(crate) fn run_layers<T, F>(table: &mut T, parallel: bool, mut progress: F) -> ControlFlow<()>
+where
+    T: Layered,
+    F: FnMut(u8) -> ControlFlow<()>,
+{
+    let (cells, width) = (table.cells(), table.width());
+
+    for height in 1..=cells {
+        let boards = table.symmetries().layer(cells - height).collect_vec();
+        let mut rows = vec![T::Entry::default(); boards.len() * width];
+
+        let solver = &*table;
+        let solve = |scratch: &mut T::Scratch, (&board, row)| solver.solve_board(board, scratch, row);
+        if parallel {
+            boards
+                .par_iter()
+                .zip(rows.par_chunks_mut(width))
+                .for_each_init(T::Scratch::default, solve);
+        } else {
+            let mut scratch = T::Scratch::default();
+            boards
+                .iter()
+                .zip(rows.chunks_mut(width))
+                .for_each(|board| solve(&mut scratch, board));
+        }
+
+        for (&board, row) in boards.iter().zip(rows.chunks_exact(width)) {
+            table.store(board, row);
+        }
+
+        progress(height)?;
+    }
+
+    ControlFlow::Continue(())
+}
@@ -1,6 +1,18 @@
+pub mod board;
 pub mod deterministic;
+pub mod distribution;
+pub mod figure_set;
+pub mod heuristic;
 pub mod solver;
 pub mod jigsaw;
+pub mod mcts;
+pub mod response;
+pub mod tiered;
+pub mod tournament;
 
 mod app;
+mod history;
+mod layers;
+mod practice;
+mod symmetry;
 pub use app::App;
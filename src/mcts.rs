@@ -0,0 +1,402 @@
+use std::time::{Duration, Instant};
+
+use crate::jigsaw::{Action, Dimensions, FigureDistribution, Jigsaw, Orientations, Placement};
+use crate::solver::Solver;
+use rand::distributions::WeightedIndex;
+use rand::prelude::*;
+
+/// Rounds a random playout lasts at most, so boards no figure of the set can
+/// finish still end. Such playouts are charged the rounds they lasted.
+const PLAYOUT_ROUNDS: u32 = 128;
+
+/// How long `Mcts` searches before answering.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Budget {
+    /// Number of playouts from the current game.
+    Iterations(u32),
+    /// Wall clock time, at least one playout being done whatever it is.
+    Time(Duration),
+}
+
+impl Default for Budget {
+    fn default() -> Self {
+        Budget::Iterations(10_000)
+    }
+}
+
+/// Solver that samples the game tree instead of solving every board, so it
+/// needs no table and works from any `Jigsaw`, at the cost of an answer that
+/// is only as good as its `Budget`.
+///
+/// The tree alternates decision nodes, a board and the figure to place, and
+/// chance nodes, the board after a placement, whose children are the figures
+/// that can be drawn next, sampled from the distribution. Placements are
+/// picked with UCT minimizing the pieces left, and new boards are evaluated by
+/// playing random placements until the board is full.
+///
+/// Every search is seeded from the game, so the same game with an iteration
+/// budget always gets the same answer.
+pub struct Mcts {
+    figures: FigureDistribution,
+    budget: Budget,
+    exploration: f64,
+    seed: u64,
+}
+
+impl Default for Mcts {
+    fn default() -> Self {
+        Self::new(FigureDistribution::default())
+    }
+}
+
+impl Mcts {
+    /// Searches with the default budget, drawing figures from `figures`,
+    /// which must have a probability for every figure of the games it solves.
+    pub fn new(figures: FigureDistribution) -> Self {
+        Self {
+            figures,
+            budget: Budget::default(),
+            exploration: 1.0,
+            seed: 0,
+        }
+    }
+
+    pub fn with_budget(self, budget: Budget) -> Self {
+        Self { budget, ..self }
+    }
+
+    /// Weight of the exploration term of UCT, relative to the pieces left from
+    /// the node being expanded.
+    pub fn with_exploration(self, exploration: f64) -> Self {
+        assert!(exploration.is_finite() && exploration >= 0.0);
+        Self { exploration, ..self }
+    }
+
+    pub fn with_seed(self, seed: u64) -> Self {
+        Self { seed, ..self }
+    }
+
+    pub fn figures(&self) -> &FigureDistribution {
+        &self.figures
+    }
+
+    pub fn budget(&self) -> Budget {
+        self.budget
+    }
+
+    /// Every placement of the current figure tried from `game`, skipping
+    /// included, with the number of playouts through it and the mean pieces
+    /// they needed to finish the board, counting this one. The recommended
+    /// placement comes first.
+    pub fn evaluate(&self, game: &Jigsaw) -> Vec<(Placement, u32, f32)> {
+        assert_eq!(self.figures.probabilities().len(), game.figures.len());
+        if game.has_finished() {
+            return vec![(Placement::default(), 0, 0.0)];
+        }
+
+        let mut search = Search::new(self, game);
+        let started = Instant::now();
+        let mut iterations = 0;
+        loop {
+            search.visit(0, 0);
+            iterations += 1;
+
+            let done = match self.budget {
+                Budget::Iterations(n) => iterations >= n,
+                Budget::Time(limit) => started.elapsed() >= limit,
+            };
+            if done {
+                break;
+            }
+        }
+
+        let mut values = search.nodes[0]
+            .children
+            .iter()
+            .map(|c| (c.placement, c.visits, c.mean() as f32))
+            .collect::<Vec<_>>();
+        // the most visited placement is the most reliable one.
+        values.sort_by(|a, b| b.1.cmp(&a.1).then(a.2.total_cmp(&b.2)));
+        values
+    }
+}
+
+impl Solver for Mcts {
+    fn solve(&self, game: &Jigsaw) -> Action {
+        let fixed = Jigsaw {
+            orientations: Orientations::Fixed,
+            ..*game
+        };
+        self.evaluate(&fixed)[0].0.action
+    }
+
+    fn placement(&self, game: &Jigsaw) -> Placement {
+        self.evaluate(game)[0].0
+    }
+}
+
+struct Decision {
+    board: u32,
+    figure: u8,
+    visits: u32,
+    cost: f64,
+    children: Vec<Chance>,
+}
+
+struct Chance {
+    placement: Placement,
+    board: u32,
+    visits: u32,
+    cost: f64,
+    // decision node reached for each figure drawn next, once sampled.
+    children: Vec<Option<usize>>,
+}
+
+impl Chance {
+    fn mean(&self) -> f64 {
+        self.cost / self.visits as f64
+    }
+}
+
+// orientation of a figure, the figure placed at `(0, 0)` and its maximum
+// offsets.
+type Variant = (u8, u32, (u8, u8));
+
+struct Search<'a> {
+    mcts: &'a Mcts,
+    dims: Dimensions,
+    terminal: u32,
+    // every orientation of every figure that fits the board.
+    variants: Vec<Vec<Variant>>,
+    draw: WeightedIndex<f32>,
+    rng: StdRng,
+    nodes: Vec<Decision>,
+    scratch: Vec<u32>,
+}
+
+impl<'a> Search<'a> {
+    fn new(mcts: &'a Mcts, game: &Jigsaw) -> Self {
+        let dims = game.dims;
+        let variants = game
+            .figures
+            .iter()
+            .map(|figure| {
+                game.orientations
+                    .variants(figure)
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(o, v)| Some((o as u8, v.value::<u32>(dims), v.max_offset(dims)?)))
+                    .collect()
+            })
+            .collect();
+
+        let seed = mcts.seed ^ (game.occupied() as u64) << 8 ^ game.figure as u64;
+        let root = Decision {
+            board: game.occupied(),
+            figure: game.figure,
+            visits: 0,
+            cost: 0.0,
+            children: Vec::new(),
+        };
+
+        Self {
+            mcts,
+            dims,
+            terminal: dims.terminal_state(),
+            variants,
+            draw: WeightedIndex::new(mcts.figures.probabilities()).unwrap(),
+            rng: StdRng::seed_from_u64(seed),
+            nodes: vec![root],
+            scratch: Vec::new(),
+        }
+    }
+
+    // boards `figure` can lead to from `board`, along with the placements.
+    fn placements(&self, board: u32, figure: u8) -> impl Iterator<Item = (Placement, u32)> + '_ {
+        let dims = self.dims;
+        self.variants[figure as usize].iter().flat_map(move |&(orientation, value, max_offset)| {
+            (0..=max_offset.0)
+                .flat_map(move |x| (0..=max_offset.1).map(move |y| (x, y)))
+                .map(move |offsets| (offsets, value >> dims.offset_to_action(offsets)))
+                .filter(move |&(_, figure)| board & figure == 0)
+                .map(move |(offsets, figure)| {
+                    let placement = Placement {
+                        orientation,
+                        action: offsets.into(),
+                    };
+                    (placement, board | figure)
+                })
+        })
+    }
+
+    // pieces used from decision node `node` until the board is full in one
+    // more playout, growing the tree by at most one decision node.
+    fn visit(&mut self, node: usize, depth: u32) -> f64 {
+        let (board, figure) = (self.nodes[node].board, self.nodes[node].figure);
+        if board == self.terminal {
+            return 0.0;
+        }
+        if depth >= PLAYOUT_ROUNDS {
+            return self.playout(board, figure);
+        }
+
+        if self.nodes[node].children.is_empty() {
+            let skip = Placement::default();
+            let k = self.variants.len();
+            let children = self
+                .placements(board, figure)
+                .chain([(skip, board)])
+                .map(|(placement, board)| Chance {
+                    placement,
+                    board,
+                    visits: 0,
+                    cost: 0.0,
+                    children: vec![None; k],
+                })
+                .collect();
+            self.nodes[node].children = children;
+        }
+
+        let c = self.select(node);
+        let next = self.nodes[node].children[c].board;
+        let cost = 1.0
+            + if next == self.terminal {
+                0.0
+            } else {
+                let drawn = self.draw.sample(&mut self.rng);
+                match self.nodes[node].children[c].children[drawn] {
+                    Some(child) => self.visit(child, depth + 1),
+                    None => {
+                        self.nodes.push(Decision {
+                            board: next,
+                            figure: drawn as u8,
+                            visits: 0,
+                            cost: 0.0,
+                            children: Vec::new(),
+                        });
+                        let child = self.nodes.len() - 1;
+                        self.nodes[node].children[c].children[drawn] = Some(child);
+
+                        let cost = self.playout(next, drawn as u8);
+                        self.nodes[child].visits += 1;
+                        self.nodes[child].cost += cost;
+                        cost
+                    }
+                }
+            };
+
+        let decision = &mut self.nodes[node];
+        decision.visits += 1;
+        decision.cost += cost;
+        decision.children[c].visits += 1;
+        decision.children[c].cost += cost;
+        cost
+    }
+
+    // placements not tried yet come first, then the lowest bound on the
+    // pieces left, scaled by the mean pieces left from `node`.
+    fn select(&self, node: usize) -> usize {
+        let decision = &self.nodes[node];
+        if let Some(c) = decision.children.iter().position(|c| c.visits == 0) {
+            return c;
+        }
+
+        let scale = self.mcts.exploration * decision.cost / decision.visits as f64;
+        let log = (decision.visits as f64).ln();
+        let bound = |c: &Chance| c.mean() - scale * (log / c.visits as f64).sqrt();
+
+        (0..decision.children.len())
+            .min_by(|&a, &b| bound(&decision.children[a]).total_cmp(&bound(&decision.children[b])))
+            .unwrap()
+    }
+
+    // pieces used placing each figure drawn at random, skipping only those
+    // that do not fit.
+    fn playout(&mut self, mut board: u32, mut figure: u8) -> f64 {
+        let mut scratch = std::mem::take(&mut self.scratch);
+        let mut rounds = 0;
+
+        while board != self.terminal && rounds < PLAYOUT_ROUNDS {
+            scratch.clear();
+            scratch.extend(self.placements(board, figure).map(|(_, next)| next));
+            if let Some(&next) = scratch.choose(&mut self.rng) {
+                board = next;
+            }
+
+            rounds += 1;
+            figure = self.draw.sample(&mut self.rng) as u8;
+        }
+
+        self.scratch = scratch;
+        rounds as f64
+    }
+}
+
+#[cfg(test)]
+mod mcts_test {
+    use super::*;
+    use crate::deterministic::Deterministic;
+
+    #[test]
+    fn test_budget() {
+        // every iteration is one playout through a placement of the root.
+        let game = Jigsaw::default();
+        let solver = Mcts::default().with_budget(Budget::Iterations(300));
+        let values = solver.evaluate(&game);
+        assert_eq!(values.iter().map(|v| v.1).sum::<u32>(), 300);
+        assert_eq!(solver.evaluate(&game), values);
+
+        let limit = Duration::from_millis(50);
+        let solver = Mcts::default().with_budget(Budget::Time(limit));
+        let started = Instant::now();
+        let values = solver.evaluate(&game);
+        assert!(started.elapsed() < limit + Duration::from_secs(1));
+        assert!(values.iter().map(|v| v.1).sum::<u32>() >= 1);
+    }
+
+    #[test]
+    fn test_matches_table() {
+        // with enough playouts the chosen placements are as good as the best
+        // ones on a small board.
+        let dims = Dimensions::new(2, 4);
+        let mut strategy = Deterministic::with_dimensions(dims, FigureDistribution::default());
+        strategy.run();
+        let solver = Mcts::default().with_budget(Budget::Iterations(4000));
+
+        let mut game = Jigsaw::new(dims);
+        for figure in 0..6 {
+            game.figure = figure;
+            let mut next = game;
+            next.perform_action(solver.solve(&game));
+            let mut best = game;
+            best.perform_action(strategy.solve(&game));
+
+            let value = |game: &Jigsaw| {
+                let mut game = *game;
+                (0..6).map(|f| {
+                    game.figure = f;
+                    strategy.expected_pieces(&game)
+                }).sum::<f32>() / 6.0
+            };
+            assert!((value(&next) - value(&best)).abs() < 1e-3, "figure {}", figure);
+        }
+    }
+
+    #[test]
+    fn test_orientations() {
+        // the root has a child for every orientation of every placement,
+        // the most visited one first.
+        let game = Jigsaw {
+            figure: 1,
+            orientations: Orientations::Rotations,
+            ..Default::default()
+        };
+        let solver = Mcts::default().with_budget(Budget::Iterations(500));
+
+        let values = solver.evaluate(&game);
+        assert_eq!(values.len(), game.legal_placements().len());
+        assert!(values.iter().any(|v| v.0.orientation == 1));
+        assert!(values.windows(2).all(|w| w[0].1 >= w[1].1));
+        assert_eq!(solver.placement(&game), values[0].0);
+    }
+}
@@ -0,0 +1,168 @@
+use crate::jigsaw::Placement;
+
+// expected values of tied actions may differ in the last bits.
+const TOLERANCE: f32 = 1e-4;
+
+/// Comparison of a move against the strategy's best placement, in expected
+/// pieces left to use including the current one.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Grade {
+    pub placement: Placement,
+    pub best: Placement,
+    pub expected: f32,
+    pub best_expected: f32,
+}
+
+impl Grade {
+    /// Grades `placement` against `values`, every legal placement with its
+    /// expected pieces left best first as `Deterministic::action_values`
+    /// returns them, `None` if it is not legal there.
+    pub fn new(values: &[(Placement, f32)], placement: Placement) -> Option<Self> {
+        let &(best, best_expected) = values.first()?;
+        let &(_, expected) = values.iter().find(|(p, _)| *p == placement)?;
+
+        Some(Self {
+            placement,
+            best,
+            expected,
+            best_expected,
+        })
+    }
+
+    pub fn loss(&self) -> f32 {
+        (self.expected - self.best_expected).max(0.0)
+    }
+
+    pub fn is_optimal(&self) -> bool {
+        self.loss() <= TOLERANCE
+    }
+}
+
+/// Score accumulated over the practice moves of a session.
+#[derive(Default)]
+pub struct Session {
+    grades: Vec<Grade>,
+}
+
+impl Session {
+    pub fn record(&mut self, grade: Grade) {
+        self.grades.push(grade);
+    }
+
+    /// Takes back `grade` when it is the last move recorded, as happens
+    /// when the move is undone. Moves graded before the score was reset are
+    /// no longer part of it and are left alone.
+    pub fn undo(&mut self, grade: &Grade) {
+        if self.grades.last() == Some(grade) {
+            self.grades.pop();
+        }
+    }
+
+    pub fn moves(&self) -> u32 {
+        self.grades.len() as u32
+    }
+
+    pub fn optimal(&self) -> u32 {
+        self.grades.iter().filter(|g| g.is_optimal()).count() as u32
+    }
+
+    /// Pieces lost over the moves that were not optimal.
+    pub fn regret(&self) -> f32 {
+        self.grades.iter().filter(|g| !g.is_optimal()).map(Grade::loss).sum()
+    }
+
+    pub fn last(&self) -> Option<&Grade> {
+        self.grades.last()
+    }
+
+    pub fn accuracy(&self) -> Option<f32> {
+        (self.moves() > 0).then(|| self.optimal() as f32 / self.moves() as f32)
+    }
+}
+
+#[cfg(test)]
+mod practice_test {
+    use super::*;
+    use crate::jigsaw::Action;
+
+    fn place(x: u8, y: u8) -> Placement {
+        Placement {
+            orientation: 0,
+            action: Action::Place { x, y },
+        }
+    }
+
+    const SKIP: Placement = Placement {
+        orientation: 0,
+        action: Action::Skip,
+    };
+
+    fn values() -> Vec<(Placement, f32)> {
+        vec![(place(1, 0), 7.0), (place(2, 0), 7.00001), (place(0, 0), 7.5), (SKIP, 8.0)]
+    }
+
+    #[test]
+    fn test_grade() {
+        let values = values();
+
+        let grade = Grade::new(&values, place(0, 0)).unwrap();
+        assert_eq!(grade.best, place(1, 0));
+        assert_eq!(grade.loss(), 0.5);
+        assert!(!grade.is_optimal());
+
+        assert!(Grade::new(&values, place(1, 0)).unwrap().is_optimal());
+        assert!(Grade::new(&values, place(2, 0)).unwrap().is_optimal());
+        assert!(Grade::new(&values, place(3, 0)).is_none());
+        assert!(Grade::new(&[], SKIP).is_none());
+    }
+
+    #[test]
+    fn test_grade_orientations() {
+        // the same cell is worth more with the figure turned.
+        let turned = Placement { orientation: 1, ..place(0, 0) };
+        let values = vec![(turned, 7.0), (place(0, 0), 7.5), (SKIP, 8.0)];
+
+        let grade = Grade::new(&values, place(0, 0)).unwrap();
+        assert_eq!(grade.best, turned);
+        assert_eq!(grade.loss(), 0.5);
+
+        assert!(Grade::new(&values, turned).unwrap().is_optimal());
+        assert!(Grade::new(&values, Placement { orientation: 2, ..turned }).is_none());
+    }
+
+    #[test]
+    fn test_session() {
+        let values = values();
+        let mut session = Session::default();
+        assert_eq!(session.accuracy(), None);
+
+        for placement in [place(1, 0), place(0, 0), SKIP, place(2, 0)] {
+            session.record(Grade::new(&values, placement).unwrap());
+        }
+
+        assert_eq!(session.moves(), 4);
+        assert_eq!(session.accuracy(), Some(0.5));
+        assert_eq!(session.regret(), 1.5);
+        assert_eq!(session.last().unwrap().placement.action, Action::Place { x: 2, y: 0 });
+    }
+
+    #[test]
+    fn test_session_undo() {
+        let values = values();
+        let mut session = Session::default();
+        let good = Grade::new(&values, place(1, 0)).unwrap();
+        let bad = Grade::new(&values, SKIP).unwrap();
+        session.record(good);
+        session.record(bad);
+
+        session.undo(&bad);
+        assert_eq!(session.moves(), 1);
+        assert_eq!(session.regret(), 0.0);
+        assert_eq!(session.last(), Some(&good));
+
+        // moves graded before a reset are not part of the score.
+        let mut session = Session::default();
+        session.undo(&good);
+        assert_eq!(session.moves(), 0);
+    }
+}
@@ -0,0 +1,130 @@
+use crate::deterministic::Deterministic;
+use crate::jigsaw::{Action, Jigsaw};
+use crate::solver::Solver;
+
+/// Answer of the solver for a single game state, meant to be consumed by
+/// other tools. With the `serde` feature it serializes to JSON as
+///
+/// ```json
+/// {
+///   "state": {
+///     "board": 15728640, "figure": 2, "round": 1,
+///     "dims": { "rows": 4, "columns": 6 }
+///   },
+///   "action": [1, 0],
+///   "orientation": 0,
+///   "values": [
+///     { "action": [1, 0], "orientation": 0, "expected_pieces": 6.93 },
+///     { "action": null, "orientation": 0, "expected_pieces": 7.85 }
+///   ]
+/// }
+/// ```
+///
+/// - `state`: the queried `Jigsaw`, `board` being the bit mask of filled
+///   cells and `dims` the size of the board, 4x6 when missing. Blocked cells
+///   are given by an `obstacles` bit mask, omitted when there are none.
+///   Only games of the standard figures can be read back, see
+///   `Jigsaw::figures`.
+/// - `action`: recommended `Action`, the `[x, y]` column and row of the top
+///   left corner of the figure's bounding box, `null` when skipping.
+/// - `orientation`: how the figure is turned before placing it, an index
+///   into `Jigsaw::variants`, always `0` when figures are placed as drawn or
+///   skipped.
+/// - `values`: every legal action in every orientation, in `Action::index`
+///   and then orientation order, with the expected number of pieces left to
+///   use including the current one. The value is `null` when the board
+///   cannot be finished after that action.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SolverResponse {
+    pub state: Jigsaw,
+    pub action: Action,
+    pub orientation: u8,
+    pub values: Vec<ActionValue>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ActionValue {
+    pub action: Action,
+    pub orientation: u8,
+    pub expected_pieces: Option<f32>,
+}
+
+impl SolverResponse {
+    pub fn new(strategy: &Deterministic, game: &Jigsaw) -> Self {
+        let mut values = strategy
+            .action_values(game)
+            .into_iter()
+            .map(|(placement, expected)| ActionValue {
+                action: placement.action,
+                orientation: placement.orientation,
+                expected_pieces: expected.is_finite().then_some(expected),
+            })
+            .collect::<Vec<_>>();
+        values.sort_by_key(|v| (v.action.index(game.dims), v.orientation));
+
+        let placement = strategy.placement(game);
+        Self {
+            state: *game,
+            action: placement.action,
+            orientation: placement.orientation,
+            values,
+        }
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod response_test {
+    use super::*;
+    use crate::jigsaw::{Dimensions, FigureDistribution};
+
+    #[test]
+    fn test_json_schema() {
+        let dims = Dimensions::new(2, 3);
+        let mut strategy = Deterministic::with_dimensions(dims, FigureDistribution::default());
+        strategy.run();
+
+        // the single cell figure fills the last hole.
+        let state: Jigsaw = "###/##. 0 3".parse().unwrap();
+        let response = SolverResponse::new(&strategy, &state);
+        assert_eq!(response.action, Action::Place { x: 2, y: 1 });
+
+        let json = serde_json::to_value(&response).unwrap();
+        assert_eq!(serde_json::from_value::<SolverResponse>(json.clone()).unwrap(), response);
+        let skip = response.values[1].expected_pieces.unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "state": {
+                    "board": 62, "figure": 0, "round": 3,
+                    "dims": { "rows": 2, "columns": 3 }
+                },
+                "action": [2, 1],
+                "orientation": 0,
+                "values": [
+                    { "action": [2, 1], "orientation": 0, "expected_pieces": 1.0 },
+                    { "action": null, "orientation": 0, "expected_pieces": skip }
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn test_reject_invalid_state() {
+        let json = r#"{ "board": 16777216, "figure": 0, "round": 0 }"#;
+        assert!(serde_json::from_str::<Jigsaw>(json).is_err());
+
+        let json = r#"{ "board": 0, "figure": 6, "round": 0 }"#;
+        assert!(serde_json::from_str::<Jigsaw>(json).is_err());
+
+        let json = r#"{ "board": 15728640, "figure": 2, "round": 1 }"#;
+        assert_eq!(serde_json::from_str::<Jigsaw>(json).unwrap().board, 15728640);
+
+        let json = r#"{ "board": 33554431, "figure": 0, "round": 0, "dims": { "rows": 5, "columns": 5 } }"#;
+        assert!(serde_json::from_str::<Jigsaw>(json).unwrap().has_finished());
+
+        let json = r#"{ "board": 0, "figure": 0, "round": 0, "dims": { "rows": 5, "columns": 7 } }"#;
+        assert!(serde_json::from_str::<Jigsaw>(json).is_err());
+    }
+}
@@ -1,5 +1,15 @@
-use crate::jigsaw::Jigsaw;
+use crate::jigsaw::{Action, Jigsaw, Placement};
 
 pub trait Solver {
-    fn solve(&self, game: &Jigsaw) -> u8;
+    fn solve(&self, game: &Jigsaw) -> Action;
+
+    /// Recommended placement for games where figures may be turned, see
+    /// `Jigsaw::orientations`. Solvers unaware of orientations place the
+    /// figure as drawn.
+    fn placement(&self, game: &Jigsaw) -> Placement {
+        Placement {
+            orientation: 0,
+            action: self.solve(game),
+        }
+    }
 }
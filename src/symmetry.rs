@@ -0,0 +1,269 @@
+use crate::figure_set::FigureSet;
+use crate::jigsaw::{Dimensions, Figure, Orientations};
+
+/// Mirror image of the board, left to right (`flip_x`), top to bottom
+/// (`flip_y`) or both, the latter being the half turn. Boards related by a
+/// symmetry play the same as long as every figure is mapped to a figure of
+/// the set drawn with the same probability.
+pub(crate) struct Symmetry {
+    flip_x: bool,
+    flip_y: bool,
+    // figure `f` in orientation `t` becomes figure `figures[f]` in orientation
+    // `turns[f][t]`, and `inverse_turns` undoes it for the mapped figure.
+    figures: Vec<u8>,
+    turns: Vec<Vec<u8>>,
+    inverse_turns: Vec<Vec<u8>>,
+}
+
+impl Symmetry {
+    fn new(flip_x: bool, flip_y: bool, set: &FigureSet, orientations: Orientations) -> Option<Self> {
+        let figures = set.figures();
+        let probabilities = set.distribution().probabilities();
+        let variants = figures.iter().map(|f| orientations.variants(f)).collect::<Vec<_>>();
+
+        let mut symmetry = Self {
+            flip_x,
+            flip_y,
+            figures: Vec::with_capacity(figures.len()),
+            turns: Vec::with_capacity(figures.len()),
+            inverse_turns: variants.iter().map(|v| vec![0; v.len()]).collect(),
+        };
+
+        let mut used = vec![false; figures.len()];
+        for (f, figure) in figures.iter().enumerate() {
+            let image = symmetry.figure(figure);
+            let mapped = (0..figures.len()).find(|&g| {
+                !used[g] && probabilities[g] == probabilities[f] && variants[g].contains(&image)
+            })?;
+            used[mapped] = true;
+
+            let turns = variants[f]
+                .iter()
+                .map(|v| {
+                    let image = symmetry.figure(v);
+                    variants[mapped].iter().position(|w| *w == image).map(|t| t as u8)
+                })
+                .collect::<Option<Vec<_>>>()?;
+            for (t, &turn) in turns.iter().enumerate() {
+                symmetry.inverse_turns[mapped][turn as usize] = t as u8;
+            }
+
+            symmetry.figures.push(mapped as u8);
+            symmetry.turns.push(turns);
+        }
+
+        Some(symmetry)
+    }
+
+    fn figure(&self, figure: &Figure) -> Figure {
+        let mut figure = *figure;
+        if self.flip_x {
+            figure = figure.reflected();
+        }
+        if self.flip_y {
+            figure = figure.reflected().rotated().rotated();
+        }
+        figure
+    }
+
+    /// Image of `board`, see `Board` for the layout.
+    pub fn board(&self, board: u32, dims: Dimensions) -> u32 {
+        // top to bottom is left to right followed by the half turn, which
+        // reverses the order of every cell.
+        let board = if self.flip_x != self.flip_y {
+            flip_columns(board, dims)
+        } else {
+            board
+        };
+
+        if self.flip_y {
+            board.reverse_bits() >> (u32::BITS - dims.cells() as u32)
+        } else {
+            board
+        }
+    }
+
+    /// Image of placing a figure at `action`, the figure being bounded by
+    /// `max_offset`, see `Figure::max_offset`. Flips keep the bounding box of
+    /// the figure, so `max_offset` is the same for its image.
+    pub fn action(&self, action: u8, max_offset: (u8, u8), dims: Dimensions) -> u8 {
+        if action == dims.skip_action() {
+            return action;
+        }
+
+        let (mut x, mut y) = dims.action_to_offsets(action);
+        if self.flip_x {
+            x = max_offset.0 - x;
+        }
+        if self.flip_y {
+            y = max_offset.1 - y;
+        }
+        dims.offset_to_action((x, y))
+    }
+
+    pub fn figure_index(&self, figure: usize) -> usize {
+        self.figures[figure] as usize
+    }
+
+    /// Orientation of the original figure for orientation `turn` of the
+    /// mapped figure `mapped`.
+    pub fn inverse_turn(&self, mapped: usize, turn: u8) -> u8 {
+        self.inverse_turns[mapped][turn as usize]
+    }
+}
+
+fn flip_columns(board: u32, dims: Dimensions) -> u32 {
+    let (rows, columns, cells) = (dims.rows as u32, dims.columns as u32, dims.cells() as u32);
+    let mask = ((1u64 << rows) - 1) as u32;
+
+    (0..columns).fold(0, |flipped, x| {
+        let column = (board >> (cells - (x + 1) * rows)) & mask;
+        flipped | column << (cells - (columns - x) * rows)
+    })
+}
+
+/// Symmetries of a board size and figure set, used to store a single board
+/// out of every set of equivalent boards, the smallest one. Canonical boards
+/// are numbered in increasing order through a bitmap with the number of
+/// canonical boards before each word.
+pub(crate) struct Symmetries {
+    dims: Dimensions,
+    list: Vec<Symmetry>,
+    words: Vec<u64>,
+    ranks: Vec<u32>,
+    boards: usize,
+}
+
+impl Symmetries {
+    /// Every symmetry the figure set allows on a board of `dims`, none when
+    /// `reduce` is false, in which case every board is canonical.
+    pub fn new(dims: Dimensions, set: &FigureSet, orientations: Orientations, reduce: bool) -> Self {
+        let list = if reduce {
+            [(true, false), (false, true), (true, true)]
+                .into_iter()
+                .filter_map(|(flip_x, flip_y)| Symmetry::new(flip_x, flip_y, set, orientations))
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        let mut symmetries = Self {
+            dims,
+            list,
+            words: Vec::new(),
+            ranks: Vec::new(),
+            boards: 1 << dims.cells(),
+        };
+        if symmetries.list.is_empty() {
+            return symmetries;
+        }
+
+        let total = 1u64 << dims.cells();
+        symmetries.words = vec![0; total.div_ceil(64) as usize];
+        for board in 0..total {
+            let board = board as u32;
+            if symmetries.canonical(board).0 == board {
+                symmetries.words[board as usize / 64] |= 1 << (board % 64);
+            }
+        }
+
+        let mut count = 0;
+        symmetries.ranks = symmetries
+            .words
+            .iter()
+            .map(|word| {
+                let rank = count;
+                count += word.count_ones();
+                rank
+            })
+            .collect();
+        symmetries.boards = count as usize;
+        symmetries
+    }
+
+    pub fn is_reduced(&self) -> bool {
+        !self.list.is_empty()
+    }
+
+    /// Number of canonical boards.
+    pub fn boards(&self) -> usize {
+        self.boards
+    }
+
+    /// Bytes used to number the canonical boards.
+    pub fn bytes(&self) -> usize {
+        self.words.len() * 8 + self.ranks.len() * 4
+    }
+
+    #[inline(always)]
+    pub fn is_canonical(&self, board: u32) -> bool {
+        self.list.is_empty() || self.words[board as usize / 64] & 1 << (board % 64) != 0
+    }
+
+    /// Canonical boards with exactly `filled` cells, in increasing order.
+    pub fn layer(&self, filled: u8) -> impl Iterator<Item = u32> + '_ {
+        let end = 1u64 << self.dims.cells();
+        // next integer with the same number of bits set.
+        let next = |board: &u64| {
+            let lowest = board & board.wrapping_neg();
+            let ripple = board + lowest;
+            let next = ripple | (((board ^ ripple) >> 2) / lowest.max(1));
+            (*board != 0).then_some(next)
+        };
+
+        std::iter::successors(Some((1u64 << filled) - 1), next)
+            .take_while(move |&board| board < end)
+            .map(|board| board as u32)
+            .filter(|&board| self.is_canonical(board))
+    }
+
+    /// Canonical board equivalent to `board`, with the symmetry mapping
+    /// `board` to it, `None` when `board` is already canonical.
+    #[inline(always)]
+    pub fn canonical(&self, board: u32) -> (u32, Option<&Symmetry>) {
+        self.list.iter().fold((board, None), |best, symmetry| {
+            let image = symmetry.board(board, self.dims);
+            if image < best.0 { (image, Some(symmetry)) } else { best }
+        })
+    }
+
+    /// Position of the canonical `board` among every canonical board.
+    #[inline(always)]
+    pub fn index(&self, board: u32) -> usize {
+        if self.list.is_empty() {
+            return board as usize;
+        }
+
+        let word = board as usize / 64;
+        let below = self.words[word] & ((1 << (board % 64)) - 1);
+        self.ranks[word] as usize + below.count_ones() as usize
+    }
+}
+
+#[cfg(test)]
+mod symmetry_test {
+    use super::*;
+    use crate::jigsaw::{Jigsaw, STANDARD};
+
+    #[test]
+    fn test_standard_symmetries() {
+        // only the half turn keeps the standard figures, swapping the two
+        // L shaped ones.
+        let symmetries = Symmetries::new(STANDARD, &FigureSet::standard(), Orientations::Fixed, true);
+        assert_eq!(symmetries.list.len(), 1);
+        assert_eq!(symmetries.list[0].figures, vec![0, 1, 3, 2, 4, 5]);
+
+        let game: Jigsaw = "##..../#...../....../...... 0 0".parse().unwrap();
+        let image: Jigsaw = "....../....../.....#/....## 0 0".parse().unwrap();
+        assert_eq!(symmetries.list[0].board(game.board, STANDARD), image.board);
+
+        let mirror = Symmetry::new(true, false, &FigureSet::standard(), Orientations::Fixed);
+        assert!(mirror.is_none());
+        // the S shaped figure has no mirror image among its rotations.
+        assert!(Symmetry::new(true, false, &FigureSet::standard(), Orientations::Rotations).is_none());
+
+        let image: Jigsaw = "....##/.....#/....../...... 0 0".parse().unwrap();
+        let flip = Symmetry::new(true, false, &FigureSet::standard(), Orientations::RotationsAndReflections);
+        assert_eq!(flip.unwrap().board(game.board, STANDARD), image.board);
+    }
+}
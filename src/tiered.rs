@@ -0,0 +1,384 @@
+use std::mem;
+use std::ops::ControlFlow;
+
+use crate::deterministic::{Deterministic, TooManyCells, MAX_TABLE_CELLS};
+use crate::distribution::round_distribution;
+use crate::figure_set::FigureSet;
+use crate::jigsaw::{Action, Dimensions, FigureDistribution, Jigsaw, Orientations, STANDARD};
+use crate::layers::{self, Layered};
+use crate::solver::Solver;
+use crate::symmetry::Symmetries;
+use itertools::Itertools;
+
+/// Highest finishing round (inclusive) of each reward tier, best tier first.
+/// These match the lines drawn by the app at 10 and 25 attempts.
+pub const TIERS: [u8; 2] = [10, 24];
+
+/// Solver that maximizes the probability of finishing the game inside the
+/// best reward tier that is still reachable from the current round, breaking
+/// ties with the best probability of landing in each of the following tiers,
+/// taken on its own.
+///
+/// Unlike `Deterministic` the answer depends on `Jigsaw::round`, so the table
+/// holds, for every board, the probability of filling it with each budget of
+/// pieces below the last tier, built bottom up like the `Deterministic` one
+/// and storing a single board out of each group of mirror boards. Figures are
+/// always placed as drawn, whatever `Jigsaw::orientations` says.
+///
+/// Once every tier has passed it plays like `Deterministic`, minimizing the
+/// expected number of pieces left, whose table it builds alongside its own.
+pub struct Tiered {
+    tiers: Vec<u8>,
+    set: FigureSet,
+    dims: Dimensions,
+    probabilities: Vec<f32>,
+    symmetries: Symmetries,
+    // each figure placed at `(0, 0)` and its maximum offsets, when it fits.
+    placements: Vec<(usize, u32, (u8, u8))>,
+    fallback: Deterministic,
+}
+
+impl Default for Tiered {
+    fn default() -> Self {
+        Self::new(&TIERS)
+    }
+}
+
+impl Tiered {
+    pub fn new(tiers: &[u8]) -> Self {
+        Self::with_distribution(tiers, FigureDistribution::default())
+    }
+
+    pub fn with_distribution(tiers: &[u8], figures: FigureDistribution) -> Self {
+        Self::with_dimensions(STANDARD, tiers, figures).expect("the standard board fits a table")
+    }
+
+    pub fn with_dimensions(dims: Dimensions, tiers: &[u8], figures: FigureDistribution) -> Result<Self, TooManyCells> {
+        Self::with_figures(dims, tiers, FigureSet::standard().with_distribution(figures))
+    }
+
+    /// Solver for boards of `dims` drawing the figures of `set`, finishing
+    /// within one of `tiers`. The table needs `2^cells` boards times the last
+    /// tier of probabilities, see `Deterministic::with_figures`, so boards
+    /// with more than `MAX_TABLE_CELLS` cells are refused.
+    pub fn with_figures(dims: Dimensions, tiers: &[u8], set: FigureSet) -> Result<Self, TooManyCells> {
+        if dims.cells() > MAX_TABLE_CELLS {
+            return Err(TooManyCells(dims));
+        }
+        let mut tiers = tiers.to_vec();
+        tiers.sort_unstable();
+        tiers.dedup();
+        assert!(tiers.last().is_some_and(|&t| t > 0));
+
+        let symmetries = Symmetries::new(dims, &set, Orientations::Fixed, true);
+        let budgets = *tiers.last().unwrap() as usize;
+        let placements = set
+            .figures()
+            .iter()
+            .enumerate()
+            .filter_map(|(f_idx, f)| Some((f_idx, f.value::<u32>(dims), f.max_offset(dims)?)))
+            .collect();
+
+        Ok(Self {
+            tiers,
+            fallback: Deterministic::with_figures(dims, set.clone()),
+            set,
+            dims,
+            probabilities: vec![0.0; symmetries.boards() * budgets],
+            symmetries,
+            placements,
+        })
+    }
+
+    pub fn tiers(&self) -> &[u8] {
+        &self.tiers
+    }
+
+    pub fn dims(&self) -> Dimensions {
+        self.dims
+    }
+
+    /// Bytes used by the table and the `Deterministic` one played once every
+    /// tier has passed.
+    pub fn memory(&self) -> usize {
+        self.probabilities.len() * mem::size_of::<f32>() + self.symmetries.bytes() + self.fallback.memory()
+    }
+
+    pub fn run(&mut self) {
+        let _ = self.run_layers(false, |_| ControlFlow::Continue(()));
+        self.fallback.run();
+    }
+
+    /// Same as `run`, solving the boards of each height layer on every core,
+    /// see `Deterministic::run_parallel`.
+    pub fn run_parallel(&mut self) {
+        let _ = self.run_layers(true, |_| ControlFlow::Continue(()));
+        self.fallback.run_parallel();
+    }
+
+    fn run_layers<F>(&mut self, parallel: bool, progress: F) -> ControlFlow<()>
+    where
+        F: FnMut(u8) -> ControlFlow<()>,
+    {
+        // the full board is finished with any budget.
+        let budgets = self.budgets();
+        let terminal_index = self.symmetries.index(self.dims.terminal_state::<u32>());
+        self.probabilities[terminal_index * budgets..][..budgets].fill(1.0);
+
+        layers::run_layers(self, parallel, progress)
+    }
+
+    // budgets stored per board, from zero to the last tier, excluded.
+    fn budgets(&self) -> usize {
+        *self.tiers.last().unwrap() as usize
+    }
+
+    /// Probability of filling `board` using at most `budget` more pieces,
+    /// following the policy that maximizes exactly that probability. The
+    /// budget must be below the last tier. Obstacles are passed as part of
+    /// `board`, see `Jigsaw::occupied`.
+    pub fn probability(&self, board: u32, budget: u8) -> f32 {
+        let budgets = self.budgets();
+        assert!((budget as usize) < budgets);
+
+        let (canonical, _) = self.symmetries.canonical(board);
+        self.probabilities[self.symmetries.index(canonical) * budgets + budget as usize]
+    }
+
+    /// Probability of finishing inside `tier` when playing `action` on `game`.
+    /// Tiers that can no longer be reached are reported as zero.
+    pub fn action_probability(&self, game: &Jigsaw, action: Action, tier: u8) -> f32 {
+        let mut next = *game;
+        next.perform_action(action);
+
+        match tier.checked_sub(game.round) {
+            Some(budget) if budget > 0 => self.probability(next.occupied(), budget - 1),
+            _ => 0.0,
+        }
+    }
+
+    /// Probability of finishing inside each tier from `game` following this
+    /// policy until the end, given that the current figure is already known.
+    /// The first reachable tier gets the best probability it can have, see
+    /// `action_probability`, the following ones may get less since they only
+    /// break ties.
+    pub fn tier_probabilities(&self, game: &Jigsaw) -> Vec<f32> {
+        let last = *self.tiers.last().unwrap();
+        let pmf = round_distribution(self, game, self.set.distribution(), last);
+
+        self.tiers
+            .iter()
+            .map(|&tier| pmf[..=tier as usize].iter().sum::<f64>() as f32)
+            .collect()
+    }
+}
+
+impl Solver for Tiered {
+    fn solve(&self, game: &Jigsaw) -> Action {
+        assert_eq!(game.dims, self.dims);
+        if game.has_finished() {
+            return Action::Skip;
+        }
+        if self.tiers.last().is_some_and(|&t| t <= game.round) {
+            return self.fallback.solve(game);
+        }
+
+        // the first tier we can still land in has priority, later tiers are
+        // only evaluated to break ties, since their search trees are much
+        // larger early in the game.
+        let mut candidates = game.legal_actions();
+        for &tier in self.tiers.iter().filter(|&&t| t > game.round) {
+            if candidates.len() == 1 {
+                break;
+            }
+
+            let probabilities = candidates
+                .iter()
+                .map(|&action| self.action_probability(game, action, tier))
+                .collect_vec();
+            let best = probabilities.iter().copied().fold(0.0, f32::max);
+
+            candidates = candidates
+                .into_iter()
+                .zip(probabilities)
+                .filter(|&(_, p)| p == best)
+                .map(|(action, _)| action)
+                .collect();
+        }
+
+        candidates[0]
+    }
+}
+
+impl Layered for Tiered {
+    type Entry = f32;
+    // best probability of each figure by budget.
+    type Scratch = Vec<f32>;
+
+    fn symmetries(&self) -> &Symmetries {
+        &self.symmetries
+    }
+
+    fn cells(&self) -> u8 {
+        self.dims.cells()
+    }
+
+    fn width(&self) -> usize {
+        self.budgets()
+    }
+
+    // a figure is placed where it finishes the board with the highest
+    // probability given the budget left, skipping it keeps the board and
+    // spends a piece of the budget.
+    fn solve_board(&self, board: u32, best: &mut Vec<f32>, row: &mut [f32]) {
+        let budgets = row.len();
+        let probabilities = self.set.distribution().probabilities();
+        best.clear();
+        best.resize(probabilities.len() * budgets, 0.0);
+
+        for &(f_idx, value, max_offset) in &self.placements {
+            let best = &mut best[f_idx * budgets..][..budgets];
+            for x in 0..=max_offset.0 {
+                for y in 0..=max_offset.1 {
+                    let figure = value >> self.dims.offset_to_action((x, y));
+                    if board & figure != 0 {
+                        continue;
+                    }
+
+                    let (next, _) = self.symmetries.canonical(board | figure);
+                    let next = &self.probabilities[self.symmetries.index(next) * budgets..][..budgets];
+                    for (best, &p) in best[1..].iter_mut().zip(next) {
+                        *best = best.max(p);
+                    }
+                }
+            }
+        }
+
+        // the board is not full, so it cannot be finished without pieces.
+        row[0] = 0.0;
+        for budget in 1..budgets {
+            let skip = row[budget - 1];
+            row[budget] = probabilities
+                .iter()
+                .enumerate()
+                .map(|(f_idx, &p)| p * best[f_idx * budgets + budget].max(skip))
+                .sum();
+        }
+    }
+
+    fn store(&mut self, board: u32, row: &[f32]) {
+        let entry = self.symmetries.index(board) * row.len();
+        self.probabilities[entry..][..row.len()].copy_from_slice(row);
+    }
+}
+
+#[cfg(test)]
+mod tiered_test {
+    use super::*;
+
+    fn solver(dims: Dimensions, tiers: &[u8]) -> Tiered {
+        let mut solver = Tiered::with_dimensions(dims, tiers, FigureDistribution::default()).unwrap();
+        solver.run();
+        solver
+    }
+
+    #[test]
+    fn test_too_many_cells() {
+        let dims = Dimensions::new(4, 7);
+        let error = Tiered::with_dimensions(dims, &TIERS, FigureDistribution::default()).err();
+        assert_eq!(error, Some(TooManyCells(dims)));
+    }
+
+    #[test]
+    fn test_probability_single_hole() {
+        let solver = solver(Dimensions::new(2, 3), &[3, 6]);
+        let board = "###/##. 0 0".parse::<Jigsaw>().unwrap().board;
+
+        assert_eq!(solver.probability(board, 0), 0.0);
+        assert!((solver.probability(board, 1) - 1.0 / 6.0).abs() < 1e-6);
+
+        let miss = 5.0f32 / 6.0;
+        assert!((solver.probability(board, 2) - (1.0 - miss * miss)).abs() < 1e-6);
+        // the mirror board is solved the same.
+        let mirror = "##./### 0 0".parse::<Jigsaw>().unwrap().board;
+        assert_eq!(solver.probability(mirror, 2), solver.probability(board, 2));
+    }
+
+    #[test]
+    fn test_solve_fills_last_hole() {
+        let solver = solver(Dimensions::new(2, 3), &[3, 6]);
+        let game: Jigsaw = "###/##. 0 2".parse().unwrap();
+
+        assert_eq!(solver.solve(&game), Action::Place { x: 2, y: 1 });
+        assert_eq!(solver.tier_probabilities(&game), vec![1.0, 1.0]);
+    }
+
+    #[test]
+    fn test_unreachable_tier() {
+        let solver = solver(Dimensions::new(2, 3), &[3, 6]);
+        let game: Jigsaw = "###/##. 1 3".parse().unwrap();
+
+        assert_eq!(solver.solve(&game), Action::Skip);
+        let p = solver.tier_probabilities(&game);
+        assert_eq!(p[0], 0.0);
+        assert!(p[1] > 0.0);
+    }
+
+    #[test]
+    fn test_differs_from_deterministic() {
+        let dims = Dimensions::new(3, 4);
+        let solver = solver(dims, &[5, 10]);
+        let mut strategy = Deterministic::with_dimensions(dims, FigureDistribution::default());
+        strategy.run();
+
+        // skipping the square needs the fewest pieces on average, but
+        // placing it makes finishing within 5 rounds twice as likely.
+        let game: Jigsaw = "..../..../.##. 4 0".parse().unwrap();
+        assert_eq!(strategy.solve(&game), Action::Skip);
+        let action = solver.solve(&game);
+        assert_eq!(action, Action::Place { x: 1, y: 0 });
+        assert!(solver.action_probability(&game, action, 5) > 2.0 * solver.action_probability(&game, Action::Skip, 5));
+
+        // the empty board is solved from the first round too.
+        let game = Jigsaw::new(dims);
+        assert!(game.is_legal(solver.solve(&game)));
+        let p = solver.tier_probabilities(&game);
+        assert!(p[0] > 0.0 && p[0] < p[1]);
+    }
+
+    #[test]
+    fn test_after_last_tier() {
+        let dims = Dimensions::new(3, 4);
+        let solver = solver(dims, &[5, 10]);
+        let mut strategy = Deterministic::with_dimensions(dims, FigureDistribution::default());
+        strategy.run();
+
+        // every tier has passed, so the fewest pieces on average are played,
+        // which here means skipping the square instead of placing it first.
+        let game: Jigsaw = "..../..../.##. 4 10".parse().unwrap();
+        assert_eq!(solver.solve(&game), Action::Skip);
+        assert_eq!(solver.solve(&game), strategy.solve(&game));
+        assert_eq!(solver.tier_probabilities(&game), vec![0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_tier_probabilities_follow_policy() {
+        let dims = Dimensions::new(3, 4);
+        let solver = solver(dims, &[5, 10]);
+        let mut game = Jigsaw::new(dims);
+        game.figure = 2;
+
+        let p = solver.tier_probabilities(&game);
+        let pmf = round_distribution(&solver, &game, &FigureDistribution::default(), 10);
+        let first = pmf[..=5].iter().sum::<f64>() as f32;
+        assert!((p[0] - first).abs() < 1e-5);
+        // the first tier is the one the policy maximizes.
+        let best = solver.action_probability(&game, solver.solve(&game), 5);
+        assert!((p[0] - best).abs() < 1e-5);
+
+        // the following tier only breaks ties, so the policy may fall short
+        // of its best probability.
+        let best = solver.action_probability(&game, solver.solve(&game), 10);
+        assert!(p[1] <= best + 1e-5);
+    }
+}
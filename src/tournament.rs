@@ -0,0 +1,296 @@
+use std::fmt;
+
+use crate::jigsaw::{FigureDistribution, Jigsaw};
+use crate::solver::Solver;
+use crate::tiered::TIERS;
+use rand::prelude::*;
+
+/// Percentiles of the final round reported for every solver, besides the
+/// median.
+pub const PERCENTILES: [u8; 4] = [5, 25, 75, 95];
+
+/// Head to head comparison of solvers. Every solver plays the same games,
+/// game `i` drawing its figures from a generator seeded with `seed + i`, so a
+/// solver only gets different figures from another one when it needs more
+/// rounds, and the results can be compared game by game.
+///
+/// Games still unfinished after `max_round` rounds are stopped and counted
+/// as finished one round later, so the mean is a lower bound when there are
+/// any, see `Standing::unfinished`.
+#[derive(Clone, Debug)]
+pub struct Tournament {
+    games: u32,
+    seed: u64,
+    max_round: u8,
+    tiers: Vec<u8>,
+}
+
+impl Default for Tournament {
+    fn default() -> Self {
+        Self {
+            games: 1000,
+            seed: 0,
+            max_round: 64,
+            tiers: TIERS.to_vec(),
+        }
+    }
+}
+
+impl Tournament {
+    pub fn with_games(self, games: u32) -> Self {
+        assert!(games > 0);
+        Self { games, ..self }
+    }
+
+    pub fn with_seed(self, seed: u64) -> Self {
+        Self { seed, ..self }
+    }
+
+    pub fn with_max_round(self, max_round: u8) -> Self {
+        assert!(max_round < u8::MAX);
+        Self { max_round, ..self }
+    }
+
+    /// Highest finishing round (inclusive) of each reward tier, see `TIERS`.
+    pub fn with_tiers(self, tiers: &[u8]) -> Self {
+        let mut tiers = tiers.to_vec();
+        tiers.sort_unstable();
+        tiers.dedup();
+        Self { tiers, ..self }
+    }
+
+    /// Plays every game from `game` with each of the named `solvers`, the
+    /// current figure being the first one of every game and the following
+    /// ones drawn from `figures`.
+    pub fn run(&self, game: &Jigsaw, figures: &FigureDistribution, solvers: &[(&str, &dyn Solver)]) -> Report {
+        let rounds = solvers
+            .iter()
+            .map(|(_, solver)| {
+                (0..self.games)
+                    .map(|i| self.play(*solver, game, figures, self.seed.wrapping_add(i as u64)))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        let standings = solvers
+            .iter()
+            .zip(&rounds)
+            .map(|((name, _), rounds)| self.standing(name, rounds))
+            .collect();
+
+        let games = self.games as f64;
+        let wins = rounds
+            .iter()
+            .map(|a| {
+                rounds
+                    .iter()
+                    .map(|b| a.iter().zip(b).filter(|(a, b)| a < b).count() as f64 / games)
+                    .collect()
+            })
+            .collect();
+
+        Report {
+            games: self.games,
+            tiers: self.tiers.clone(),
+            standings,
+            wins,
+        }
+    }
+
+    // final round of a single game, `max_round + 1` when unfinished.
+    fn play(&self, solver: &dyn Solver, game: &Jigsaw, figures: &FigureDistribution, seed: u64) -> u8 {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut game = *game;
+
+        while !game.has_finished() {
+            if game.round >= self.max_round {
+                return self.max_round + 1;
+            }
+            game.perform_placement(solver.placement(&game));
+            game.set_random_figure(&mut rng, figures);
+        }
+
+        game.round
+    }
+
+    fn standing(&self, name: &str, rounds: &[u8]) -> Standing {
+        let mut sorted = rounds.to_vec();
+        sorted.sort_unstable();
+        let games = sorted.len() as f64;
+
+        // nearest rank, the smallest round at least `p` percent of the games
+        // finish in.
+        let percentile = |p: u8| {
+            let rank = (p as f64 / 100.0 * games).ceil() as usize;
+            sorted[rank.clamp(1, sorted.len()) - 1]
+        };
+
+        Standing {
+            name: name.to_string(),
+            mean: sorted.iter().map(|&r| r as f64).sum::<f64>() / games,
+            median: percentile(50),
+            percentiles: PERCENTILES
+                .iter()
+                .map(|&p| Percentile {
+                    percentile: p,
+                    round: percentile(p),
+                })
+                .collect(),
+            tiers: self
+                .tiers
+                .iter()
+                .map(|&t| sorted.iter().filter(|&&r| r <= t.min(self.max_round)).count() as f64 / games)
+                .collect(),
+            unfinished: sorted.iter().filter(|&&r| r > self.max_round).count() as f64 / games,
+        }
+    }
+}
+
+/// Results of a `Tournament`. With the `serde` feature it serializes to JSON
+/// as
+///
+/// ```json
+/// {
+///   "games": 1000,
+///   "tiers": [10, 24],
+///   "standings": [
+///     {
+///       "name": "deterministic", "mean": 16.1, "median": 16,
+///       "percentiles": [{ "percentile": 5, "round": 11 }, ...],
+///       "tiers": [0.14, 0.91], "unfinished": 0.0
+///     },
+///     ...
+///   ],
+///   "wins": [[0.0, 0.81], [0.12, 0.0]]
+/// }
+/// ```
+///
+/// - `tiers`: probability of finishing within each tier of the report.
+/// - `unfinished`: fraction of the games stopped after the last round.
+/// - `wins[a][b]`: fraction of the games solver `a` finished in fewer rounds
+///   than solver `b`, the rest being ties or losses.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Report {
+    pub games: u32,
+    pub tiers: Vec<u8>,
+    pub standings: Vec<Standing>,
+    pub wins: Vec<Vec<f64>>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Standing {
+    pub name: String,
+    pub mean: f64,
+    pub median: u8,
+    pub percentiles: Vec<Percentile>,
+    pub tiers: Vec<f64>,
+    pub unfinished: f64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Percentile {
+    pub percentile: u8,
+    pub round: u8,
+}
+
+/// Table of the standings followed by the paired win rates, the row solver
+/// beating the column one.
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self.standings.iter().map(|s| s.name.len()).max().unwrap_or(0).max(6);
+
+        writeln!(f, "{} games", self.games)?;
+        write!(f, "{:width$}  {:>6}  {:>6}", "solver", "mean", "median")?;
+        for p in PERCENTILES {
+            write!(f, "  {:>5}", format!("p{}", p))?;
+        }
+        for t in &self.tiers {
+            write!(f, "  {:>5}", format!("<={}", t))?;
+        }
+        writeln!(f, "  {:>10}", "unfinished")?;
+
+        for s in &self.standings {
+            write!(f, "{:width$}  {:>6.2}  {:>6}", s.name, s.mean, s.median)?;
+            for p in &s.percentiles {
+                write!(f, "  {:>5}", p.round)?;
+            }
+            for t in &s.tiers {
+                write!(f, "  {:>5.3}", t)?;
+            }
+            writeln!(f, "  {:>10.3}", s.unfinished)?;
+        }
+
+        writeln!(f)?;
+        write!(f, "{:width$}", "wins")?;
+        for s in &self.standings {
+            write!(f, "  {:>width$}", s.name)?;
+        }
+        writeln!(f)?;
+        for (s, wins) in self.standings.iter().zip(&self.wins) {
+            write!(f, "{:width$}", s.name)?;
+            for w in wins {
+                write!(f, "  {:>width$.3}", w)?;
+            }
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tournament_test {
+    use super::*;
+    use crate::deterministic::Deterministic;
+    use crate::heuristic::FirstLegal;
+    use crate::jigsaw::Dimensions;
+
+    #[test]
+    fn test_tournament() {
+        let dims = Dimensions::new(2, 3);
+        let mut strategy = Deterministic::with_dimensions(dims, FigureDistribution::default());
+        strategy.run();
+
+        let tournament = Tournament::default().with_games(200).with_tiers(&[3, 6]);
+        let game = Jigsaw::new(dims);
+        let solvers: [(&str, &dyn Solver); 3] = [
+            ("deterministic", &strategy),
+            ("first-legal", &FirstLegal),
+            ("again", &strategy),
+        ];
+        let report = tournament.run(&game, strategy.figures(), &solvers);
+
+        let (best, first) = (&report.standings[0], &report.standings[1]);
+        assert!(best.mean < first.mean);
+        assert!(best.tiers[0] >= first.tiers[0] && best.tiers[0] <= best.tiers[1]);
+        assert_eq!(best.unfinished, 0.0);
+        assert!(best.percentiles[0].round <= best.median && best.median <= best.percentiles[3].round);
+
+        // the same solver gets the same figures, so every game is a tie.
+        assert_eq!(report.standings[2].mean, best.mean);
+        assert_eq!((report.wins[0][2], report.wins[2][0]), (0.0, 0.0));
+        assert!(report.wins[0][1] > report.wins[1][0]);
+        assert!(report.wins[0][1] + report.wins[1][0] <= 1.0);
+
+        let text = report.to_string();
+        assert!(text.starts_with("200 games\n"));
+        assert!(text.contains("first-legal"));
+    }
+
+    #[test]
+    fn test_unfinished() {
+        // the long piece never fits, so the game never ends.
+        let game: Jigsaw = "######/######/######/#####. 1 0".parse().unwrap();
+        let tournament = Tournament::default().with_games(10).with_max_round(5);
+        let figures = FigureDistribution::new(&[0.0, 1.0, 0.0, 0.0, 0.0, 0.0]);
+        let report = tournament.run(&game, &figures, &[("first-legal", &FirstLegal)]);
+
+        assert_eq!(report.standings[0].unfinished, 1.0);
+        assert_eq!(report.standings[0].median, 6);
+        // stopped games do not count as finished within any tier.
+        assert_eq!(report.standings[0].tiers, vec![0.0, 0.0]);
+    }
+}